@@ -0,0 +1,18 @@
+use std::path::Path;
+
+/// Exposes the version of the weresocool path dependency as
+/// `WERESOCOOL_VERSION`, which the render cache hashes.
+fn main() {
+    let manifest = Path::new(env!("CARGO_MANIFEST_DIR")).join("../weresocool/Cargo.toml");
+    println!("cargo:rerun-if-changed={}", manifest.display());
+    let contents = std::fs::read_to_string(&manifest).unwrap_or_default();
+    let version = contents
+        .lines()
+        .skip_while(|line| line.trim() != "[package]")
+        .find_map(|line| {
+            let (key, value) = line.split_once('=')?;
+            (key.trim() == "version").then(|| value.trim().trim_matches('"').to_string())
+        })
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=WERESOCOOL_VERSION={}", version);
+}
@@ -8,9 +8,9 @@ pub struct Controls {
 }
 
 impl Controls {
-    pub fn init(state: Arc<Mutex<UiState>>, n_camera: usize) -> Self {
+    pub fn init(state: Arc<Mutex<UiState>>) -> Self {
         Self {
-            windows: super::Windows::init(state, n_camera),
+            windows: super::Windows::init(state),
         }
     }
 }
@@ -1,18 +1,22 @@
 use std::sync::{Arc, Mutex};
 
-use crate::{InstanceMul, UiState};
+use crate::{
+    CameraAction, CameraModeKind, CameraReactivity, InstanceMul, PathAction, ProjectionConfig,
+    StreamState, UiControl, UiState,
+};
 
 #[derive(Debug)]
 pub struct ControlsInner {
     state: Arc<Mutex<UiState>>,
-    n_camera: usize,
+    /// Text field for naming and renaming camera presets.
+    camera_name: String,
 }
 
 impl ControlsInner {
-    pub fn init(state: Arc<Mutex<UiState>>, n_camera: usize) -> Self {
+    pub fn init(state: Arc<Mutex<UiState>>) -> Self {
         Self {
             state: state.clone(),
-            n_camera,
+            camera_name: String::new(),
         }
     }
 }
@@ -53,23 +57,16 @@ impl ControlsInner {
         // ui.visuals_mut().override_text_color = Some(egui::Color32::from_rgb(235, 72, 170));
         ui.visuals_mut().override_text_color = Some(egui::Color32::GOLD);
         ui.style_mut().body_text_style = egui::TextStyle::Heading;
-        let Self { state, n_camera } = self;
+        let Self { state, camera_name } = self;
         let mut s = state.lock().unwrap();
         let mut volume = s.volume;
-        // let mut instance_mul_x = s.instance_mul.x;
-        // let mut instance_mul_y = s.instance_mul.y;
-        // let mut instance_mul_size = s.instance_mul.size;
-        let InstanceMul {
-            mut x,
-            mut y,
-            mut z,
-            mut size,
-            mut life,
-            mut length,
-        } = s.instance_mul;
+        let mut instance_mul = s.instance_mul;
 
         ui.vertical(|ui| {
             // ui.style_mut().override_text_style = Some(egui::TextStyle::Heading);
+            if let Some(status) = &s.status {
+                ui.label(status.as_str());
+            }
             ui.horizontal(|ui| {
                 if ui.button(if s.play { "Pause" } else { "Play" }).clicked() {
                     s.play = !s.play
@@ -77,6 +74,9 @@ impl ControlsInner {
                 if ui.button("Save").clicked() {
                     s.save = true
                 }
+                if ui.button("Screenshot").clicked() {
+                    s.screenshot = true
+                }
                 ui.end_row();
                 // if ui.button("Reset").clicked() {
                 // s.reset = true
@@ -91,45 +91,272 @@ impl ControlsInner {
                 ui.end_row();
 
                 // ui.label("Camera:");
-                (0..*n_camera).into_iter().for_each(|idx| {
-                    if ui
-                        .button(format!("  {}  ", (idx + 65) as u8 as char))
-                        .clicked()
-                    {
-                        s.camera_index = idx as usize;
+                let mut camera_index = s.camera_index;
+                s.cameras.iter().enumerate().for_each(|(idx, name)| {
+                    if ui.button(format!("  {}  ", name)).clicked() {
+                        camera_index = idx;
                     }
                 });
+                s.camera_index = camera_index;
                 ui.end_row();
             });
+            egui::CollapsingHeader::new("cameras")
+                .default_open(false)
+                .show(ui, |ui| {
+                    if let Some(action) =
+                        camera_presets_ui(ui, &s.cameras, s.camera_index, camera_name)
+                    {
+                        s.camera_action = Some(action);
+                    }
+                });
+            egui::CollapsingHeader::new("camera mode")
+                .default_open(false)
+                .show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.radio_value(&mut s.camera_mode, CameraModeKind::Fly, "fly");
+                        ui.radio_value(&mut s.camera_mode, CameraModeKind::Orbit, "orbit");
+                        ui.radio_value(&mut s.camera_mode, CameraModeKind::LookAt, "look at");
+                    });
+                    ui.add(egui::Slider::new(&mut s.orbit_speed, -90.0..=90.0).text("orbit speed"));
+                });
+            egui::CollapsingHeader::new("projection")
+                .default_open(false)
+                .show(ui, |ui| {
+                    projection_sliders(ui, &mut s.projection);
+                });
+            egui::CollapsingHeader::new("camera reactivity")
+                .default_open(false)
+                .show(ui, |ui| {
+                    reactivity_sliders(ui, &mut s.reactivity);
+                });
+            if s.midi.connected {
+                egui::CollapsingHeader::new("midi")
+                    .default_open(false)
+                    .show(ui, |ui| {
+                        midi_ui(ui, &mut s);
+                    });
+            }
+            egui::CollapsingHeader::new("camera path")
+                .default_open(false)
+                .show(ui, |ui| {
+                    ui.checkbox(&mut s.follow_path, "follow");
+                    ui.label(format!("keyframes: {}", s.keyframes));
+                    ui.horizontal(|ui| {
+                        if ui.button("Record").clicked() {
+                            s.path_action = Some(PathAction::Record);
+                        }
+                        if ui.button("Remove last").clicked() {
+                            s.path_action = Some(PathAction::RemoveLast);
+                        }
+                        if ui.button("Clear").clicked() {
+                            s.path_action = Some(PathAction::Clear);
+                        }
+                    });
+                });
             ui.vertical(|ui| {
-                ui.label("x:");
-                if ui.add(egui::Slider::new(&mut x, 0.0..=100.0)).changed() {
-                    s.instance_mul.x = x
+                if instance_mul_sliders(ui, &mut instance_mul) {
+                    s.instance_mul = instance_mul
                 };
-                ui.end_row();
-                ui.label("y:");
-                if ui.add(egui::Slider::new(&mut y, 0.0..=2000.0)).changed() {
-                    s.instance_mul.y = y
-                };
-                ui.label("z:");
-                if ui.add(egui::Slider::new(&mut z, 0.0..=25.0)).changed() {
-                    s.instance_mul.z = z
-                };
-                ui.end_row();
-                ui.label("size:");
-                if ui.add(egui::Slider::new(&mut size, 0.0..=500.0)).changed() {
-                    s.instance_mul.size = size
-                };
-                ui.label("life:");
-                if ui.add(egui::Slider::new(&mut life, 0.0..=10.0)).changed() {
-                    s.instance_mul.life = life
-                };
-                ui.label("length:");
-                if ui.add(egui::Slider::new(&mut length, 0.0..=25.0)).changed() {
-                    s.instance_mul.length = length
-                };
-                ui.end_row();
             });
+            ui.vertical(|ui| {
+                let global = s.instance_mul;
+                let selected = s.selected_stream;
+                s.streams.iter_mut().enumerate().for_each(|(idx, stream)| {
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut stream.mute, "M");
+                        ui.checkbox(&mut stream.solo, "S");
+                        ui.checkbox(&mut stream.hidden, "H");
+                        let name = if idx == selected {
+                            format!("> {}", stream.name)
+                        } else {
+                            stream.name.to_owned()
+                        };
+                        egui::CollapsingHeader::new(name)
+                            .id_source(format!("stream_{}", idx))
+                            .default_open(false)
+                            .show(ui, |ui| stream_ui(ui, stream, global));
+                    });
+                });
+            });
+            if !s.stems.is_empty() {
+                egui::CollapsingHeader::new("stems")
+                    .default_open(false)
+                    .show(ui, |ui| {
+                        s.stems.iter_mut().for_each(|stem| {
+                            ui.label(stem.name.as_str());
+                            ui.add(egui::Slider::new(&mut stem.gain, 0.0..=2.0));
+                        });
+                    });
+            }
+        });
+    }
+}
+
+fn camera_presets_ui(
+    ui: &mut egui::Ui,
+    cameras: &[String],
+    selected: usize,
+    camera_name: &mut String,
+) -> Option<CameraAction> {
+    let mut action = None;
+    cameras.iter().enumerate().for_each(|(idx, name)| {
+        ui.horizontal(|ui| {
+            ui.label(if idx == selected {
+                format!("> {}", name)
+            } else {
+                name.to_owned()
+            });
+            if ui.small_button("^").clicked() {
+                action = Some(CameraAction::Move(idx, -1));
+            }
+            if ui.small_button("v").clicked() {
+                action = Some(CameraAction::Move(idx, 1));
+            }
+            if cameras.len() > 1 && ui.small_button("x").clicked() {
+                action = Some(CameraAction::Delete(idx));
+            }
+        });
+    });
+    ui.text_edit_singleline(camera_name);
+    ui.horizontal(|ui| {
+        if ui.button("Add").clicked() {
+            let name = if camera_name.is_empty() {
+                format!("{}", cameras.len() + 1)
+            } else {
+                camera_name.to_owned()
+            };
+            action = Some(CameraAction::Add(name));
+        }
+        if ui.button("Rename").clicked() && !camera_name.is_empty() {
+            action = Some(CameraAction::Rename(selected, camera_name.to_owned()));
+        }
+        if ui.button("Update").clicked() {
+            action = Some(CameraAction::Update(selected));
+        }
+    });
+
+    action
+}
+
+fn midi_ui(ui: &mut egui::Ui, s: &mut UiState) {
+    let controls: Vec<(UiControl, String)> = UiControl::all(s)
+        .into_iter()
+        .map(|control| {
+            let name = control.name(s);
+            (control, name)
+        })
+        .collect();
+    let selected = s
+        .midi
+        .control
+        .as_ref()
+        .map_or_else(|| "choose a control".to_string(), |control| control.name(s));
+    let midi = &mut s.midi;
+
+    for (idx, binding) in midi.bindings.iter().enumerate() {
+        ui.horizontal(|ui| {
+            ui.label(binding);
+            if ui.small_button("x").clicked() {
+                midi.remove = Some(idx);
+            }
+        });
+    }
+    egui::ComboBox::from_label("control")
+        .selected_text(selected)
+        .show_ui(ui, |ui| {
+            for (control, name) in controls {
+                ui.selectable_value(&mut midi.control, Some(control), name);
+            }
+        });
+    if midi.learning {
+        ui.horizontal(|ui| {
+            ui.label("move a fader or press a key...");
+            if ui.button("Cancel").clicked() {
+                midi.learning = false;
+            }
         });
+    } else if ui.button("Learn").clicked() && midi.control.is_some() {
+        midi.learning = true;
     }
 }
+
+fn stream_ui(ui: &mut egui::Ui, stream: &mut StreamState, global: InstanceMul) {
+    let mut overridden = stream.instance_mul.is_some();
+    if ui.checkbox(&mut overridden, "override").changed() {
+        stream.instance_mul = if overridden { Some(global) } else { None };
+    }
+    if let Some(instance_mul) = &mut stream.instance_mul {
+        instance_mul_sliders(ui, instance_mul);
+    }
+}
+
+fn projection_sliders(ui: &mut egui::Ui, projection: &mut ProjectionConfig) {
+    let ProjectionConfig {
+        fov,
+        znear,
+        zfar,
+        orthographic,
+        height,
+    } = projection;
+    ui.checkbox(orthographic, "orthographic");
+    if *orthographic {
+        ui.add(
+            egui::Slider::new(height, 1.0..=5000.0)
+                .logarithmic(true)
+                .text("height"),
+        );
+    } else {
+        ui.add(egui::Slider::new(fov, 10.0..=150.0).text("fov"));
+    }
+    ui.add(
+        egui::Slider::new(znear, 0.01..=100.0)
+            .logarithmic(true)
+            .text("near"),
+    );
+    ui.add(
+        egui::Slider::new(zfar, 100.0..=100_000.0)
+            .logarithmic(true)
+            .text("far"),
+    );
+}
+
+fn reactivity_sliders(ui: &mut egui::Ui, reactivity: &mut CameraReactivity) {
+    let CameraReactivity {
+        shake,
+        fov_pulse,
+        dolly,
+    } = reactivity;
+    ui.add(egui::Slider::new(shake, 0.0..=2.0).text("shake"));
+    ui.add(egui::Slider::new(fov_pulse, 0.0..=2.0).text("fov pulse"));
+    ui.add(egui::Slider::new(dolly, 0.0..=2.0).text("dolly"));
+}
+
+fn instance_mul_sliders(ui: &mut egui::Ui, instance_mul: &mut InstanceMul) -> bool {
+    let InstanceMul {
+        x,
+        y,
+        z,
+        size,
+        life,
+        length,
+    } = instance_mul;
+    let mut changed = false;
+
+    ui.label("x:");
+    changed |= ui.add(egui::Slider::new(x, 0.0..=100.0)).changed();
+    ui.end_row();
+    ui.label("y:");
+    changed |= ui.add(egui::Slider::new(y, 0.0..=2000.0)).changed();
+    ui.label("z:");
+    changed |= ui.add(egui::Slider::new(z, 0.0..=25.0)).changed();
+    ui.end_row();
+    ui.label("size:");
+    changed |= ui.add(egui::Slider::new(size, 0.0..=500.0)).changed();
+    ui.label("life:");
+    changed |= ui.add(egui::Slider::new(life, 0.0..=10.0)).changed();
+    ui.label("length:");
+    changed |= ui.add(egui::Slider::new(length, 0.0..=25.0)).changed();
+    ui.end_row();
+
+    changed
+}
@@ -14,26 +14,19 @@ struct Kintaro {
 }
 
 impl Kintaro {
-    pub fn init(state: Arc<Mutex<UiState>>, n_camera: usize) -> Self {
+    pub fn init(state: Arc<Mutex<UiState>>) -> Self {
         Self::from_modules(
             state.clone(),
-            vec![Box::new(super::widget_gallery::ControlsInner::init(
-                state, n_camera,
-            ))],
-            n_camera,
+            vec![Box::new(super::widget_gallery::ControlsInner::init(state))],
         )
     }
 }
 
 impl Kintaro {
-    pub fn from_modules(
-        state: Arc<Mutex<UiState>>,
-        modules: Vec<Box<dyn Module>>,
-        n_camera: usize,
-    ) -> Self {
+    pub fn from_modules(state: Arc<Mutex<UiState>>, modules: Vec<Box<dyn Module>>) -> Self {
         let mut open = BTreeSet::new();
         open.insert(
-            super::widget_gallery::ControlsInner::init(state.clone(), n_camera)
+            super::widget_gallery::ControlsInner::init(state.clone())
                 .name()
                 .to_owned(),
         );
@@ -66,9 +59,9 @@ pub struct Windows {
 }
 
 impl Windows {
-    pub fn init(state: Arc<Mutex<UiState>>, n_camera: usize) -> Self {
+    pub fn init(state: Arc<Mutex<UiState>>) -> Self {
         Self {
-            kintaro: Kintaro::init(state, n_camera),
+            kintaro: Kintaro::init(state),
         }
     }
 
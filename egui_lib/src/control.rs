@@ -0,0 +1,176 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{InstanceMul, UiState};
+
+/// A `UiState` field that external controllers, e.g. MIDI, can drive.
+/// Faders set values, buttons toggle or select.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum UiControl {
+    Volume,
+    InstanceMul(InstanceMulField),
+    Shake,
+    FovPulse,
+    Dolly,
+    OrbitSpeed,
+    /// Selects the camera preset at the index.
+    Camera(usize),
+    PlayPause,
+    /// Toggles the stream with the name. Names, unlike indices, mean the
+    /// same stream in every composition the mapping is used with.
+    Mute(String),
+    Solo(String),
+    Hide(String),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum InstanceMulField {
+    X,
+    Y,
+    Z,
+    Size,
+    Life,
+    Length,
+}
+
+impl InstanceMulField {
+    pub const ALL: [InstanceMulField; 6] = [
+        InstanceMulField::X,
+        InstanceMulField::Y,
+        InstanceMulField::Z,
+        InstanceMulField::Size,
+        InstanceMulField::Life,
+        InstanceMulField::Length,
+    ];
+
+    /// The field's name in `InstanceMul`, e.g. `size`.
+    pub fn name(&self) -> String {
+        format!("{:?}", self).to_lowercase()
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|field| field.name() == name)
+    }
+
+    pub fn set(&self, instance_mul: &mut InstanceMul, value: f32) {
+        match self {
+            InstanceMulField::X => instance_mul.x = value,
+            InstanceMulField::Y => instance_mul.y = value,
+            InstanceMulField::Z => instance_mul.z = value,
+            InstanceMulField::Size => instance_mul.size = value,
+            InstanceMulField::Life => instance_mul.life = value,
+            InstanceMulField::Length => instance_mul.length = value,
+        }
+    }
+
+    /// The range of the field's slider.
+    pub fn range(&self) -> (f32, f32) {
+        match self {
+            InstanceMulField::X => (0.0, 100.0),
+            InstanceMulField::Y => (0.0, 2000.0),
+            InstanceMulField::Z => (0.0, 25.0),
+            InstanceMulField::Size => (0.0, 500.0),
+            InstanceMulField::Life => (0.0, 10.0),
+            InstanceMulField::Length => (0.0, 25.0),
+        }
+    }
+}
+
+impl UiControl {
+    /// Buttons act when pressed, everything else follows the value.
+    pub fn is_button(&self) -> bool {
+        matches!(
+            self,
+            UiControl::Camera(_)
+                | UiControl::PlayPause
+                | UiControl::Mute(_)
+                | UiControl::Solo(_)
+                | UiControl::Hide(_)
+        )
+    }
+
+    /// Every control of the current cameras and streams, for choosing one to
+    /// learn.
+    pub fn all(state: &UiState) -> Vec<UiControl> {
+        let mut controls = vec![UiControl::Volume];
+        controls.extend(
+            InstanceMulField::ALL
+                .iter()
+                .map(|f| UiControl::InstanceMul(*f)),
+        );
+        controls.extend(vec![
+            UiControl::Shake,
+            UiControl::FovPulse,
+            UiControl::Dolly,
+            UiControl::OrbitSpeed,
+            UiControl::PlayPause,
+        ]);
+        controls.extend((0..state.cameras.len()).map(UiControl::Camera));
+        state.streams.iter().for_each(|stream| {
+            controls.extend(vec![
+                UiControl::Mute(stream.name.to_owned()),
+                UiControl::Solo(stream.name.to_owned()),
+                UiControl::Hide(stream.name.to_owned()),
+            ])
+        });
+        controls
+    }
+
+    pub fn name(&self, state: &UiState) -> String {
+        match self {
+            UiControl::Volume => "volume".into(),
+            UiControl::InstanceMul(field) => field.name(),
+            UiControl::Shake => "shake".into(),
+            UiControl::FovPulse => "fov pulse".into(),
+            UiControl::Dolly => "dolly".into(),
+            UiControl::OrbitSpeed => "orbit speed".into(),
+            UiControl::Camera(idx) => format!(
+                "camera {}",
+                state.cameras.get(*idx).map_or("?", |name| name.as_str())
+            ),
+            UiControl::PlayPause => "play/pause".into(),
+            UiControl::Mute(stream) => format!("mute {}", stream),
+            UiControl::Solo(stream) => format!("solo {}", stream),
+            UiControl::Hide(stream) => format!("hide {}", stream),
+        }
+    }
+}
+
+impl UiState {
+    /// Applies a controller value in 0..1. Faders are scaled to their slider's
+    /// range, buttons act on values of at least 0.5.
+    pub fn apply_control(&mut self, control: &UiControl, value: f32) {
+        let value = value.clamp(0.0, 1.0);
+        let scale = |(lo, hi): (f32, f32)| lo + (hi - lo) * value;
+        let pressed = value >= 0.5;
+        match control {
+            UiControl::Volume => self.volume = value,
+            UiControl::InstanceMul(field) => {
+                field.set(&mut self.instance_mul, scale(field.range()))
+            }
+            UiControl::Shake => self.reactivity.shake = scale((0.0, 2.0)),
+            UiControl::FovPulse => self.reactivity.fov_pulse = scale((0.0, 2.0)),
+            UiControl::Dolly => self.reactivity.dolly = scale((0.0, 2.0)),
+            UiControl::OrbitSpeed => self.orbit_speed = scale((-90.0, 90.0)),
+            UiControl::Camera(idx) => {
+                if pressed && *idx < self.cameras.len() {
+                    self.camera_index = *idx
+                }
+            }
+            UiControl::PlayPause => {
+                if pressed {
+                    self.play = !self.play
+                }
+            }
+            UiControl::Mute(name) | UiControl::Solo(name) | UiControl::Hide(name) => {
+                let stream = self.streams.iter_mut().find(|stream| stream.name == *name);
+                if let (true, Some(stream)) = (pressed, stream) {
+                    match control {
+                        UiControl::Mute(_) => stream.mute = !stream.mute,
+                        UiControl::Solo(_) => stream.solo = !stream.solo,
+                        _ => stream.hidden = !stream.hidden,
+                    }
+                }
+            }
+        }
+    }
+}
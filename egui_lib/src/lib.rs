@@ -3,13 +3,18 @@
 //! The demo-code is also used in benchmarks and tests.
 
 mod apps;
+mod control;
 mod wrap_app;
 
 pub use apps::Windows; // used for tests
+pub use control::{InstanceMulField, UiControl};
 pub use egui_wgpu_backend::{RenderPass, ScreenDescriptor};
 pub use egui_winit_platform::{Platform, PlatformDescriptor};
 pub use epi;
-pub use wrap_app::{InstanceMul, UiState, WrapApp};
+pub use wrap_app::{
+    CameraAction, CameraModeKind, CameraReactivity, InstanceMul, MidiState, PathAction,
+    ProjectionConfig, StemState, StreamState, UiState, WrapApp,
+};
 
 #[test]
 fn test_egui_e2e() {
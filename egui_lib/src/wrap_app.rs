@@ -1,4 +1,6 @@
 use serde::{Deserialize, Serialize};
+
+use crate::UiControl;
 use std::sync::{Arc, Mutex};
 
 /// All the different demo apps.
@@ -7,9 +9,9 @@ pub struct Apps {
 }
 
 impl Apps {
-    fn init(state: Arc<Mutex<UiState>>, n_camera: usize) -> Self {
+    fn init(state: Arc<Mutex<UiState>>) -> Self {
         Apps {
-            controls: crate::apps::Controls::init(state.clone(), n_camera),
+            controls: crate::apps::Controls::init(state.clone()),
         }
     }
 
@@ -18,14 +20,158 @@ impl Apps {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct UiState {
     pub play: bool,
     pub volume: f32,
     pub camera_index: usize,
+    /// Names of the camera presets, in order.
+    pub cameras: Vec<String>,
+    /// Edits the presets, handled and cleared by the application.
+    pub camera_action: Option<CameraAction>,
+    /// Drive the camera along the recorded path.
+    pub follow_path: bool,
+    pub keyframes: usize,
+    pub path_action: Option<PathAction>,
+    pub camera_mode: CameraModeKind,
+    /// Degrees per second an orbiting camera turns on its own.
+    pub orbit_speed: f32,
+    pub reactivity: CameraReactivity,
+    pub projection: ProjectionConfig,
     pub instance_mul: InstanceMul,
+    pub streams: Vec<StreamState>,
+    pub selected_stream: usize,
+    pub stems: Vec<StemState>,
+    /// Progress and errors of background work, e.g. live reloading.
+    pub status: Option<String>,
     pub reset: bool,
     pub save: bool,
+    pub screenshot: bool,
+    pub midi: MidiState,
+}
+
+/// MIDI learn and the bindings of the connected input.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct MidiState {
+    pub connected: bool,
+    /// Descriptions of the bindings, in order.
+    pub bindings: Vec<String>,
+    /// The control to learn.
+    pub control: Option<UiControl>,
+    /// Binds the next message to `control`, cleared by the application once
+    /// it has.
+    pub learning: bool,
+    /// Removes the binding at the index, handled by the application.
+    pub remove: Option<usize>,
+}
+
+/// Edits of the camera presets, by index into `UiState::cameras`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum CameraAction {
+    /// Adds the current camera as a new preset with the name.
+    Add(String),
+    /// Overwrites the preset with the current camera.
+    Update(usize),
+    Rename(usize, String),
+    Delete(usize),
+    /// Moves the preset by an offset, e.g. -1 moves it up.
+    Move(usize, isize),
+}
+
+/// Edits of the keyframed camera path.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum PathAction {
+    /// Adds the current camera as a keyframe at the current time.
+    Record,
+    /// Removes the keyframe recorded last, not the latest in time.
+    RemoveLast,
+    Clear,
+}
+
+/// How the camera moves. Look at follows the selected stream.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum CameraModeKind {
+    Fly,
+    Orbit,
+    LookAt,
+}
+
+impl Default for CameraModeKind {
+    fn default() -> Self {
+        CameraModeKind::Fly
+    }
+}
+
+/// Per op stream controls, listed in the same order as the renderpasses.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StreamState {
+    pub name: String,
+    /// `None` inherits the global `InstanceMul`.
+    pub instance_mul: Option<InstanceMul>,
+    /// Muted streams are neither drawn nor, when rendering stems, heard.
+    #[serde(default)]
+    pub mute: bool,
+    /// When any stream is soloed, only soloed streams are drawn and heard.
+    #[serde(default)]
+    pub solo: bool,
+    /// Hidden streams keep consuming ops so they stay in sync, but are not drawn.
+    #[serde(default)]
+    pub hidden: bool,
+}
+
+impl StreamState {
+    pub fn init(name: String, instance_mul: Option<InstanceMul>) -> Self {
+        Self {
+            name,
+            instance_mul,
+            mute: false,
+            solo: false,
+            hidden: false,
+        }
+    }
+
+    pub fn instance_mul(&self, global: InstanceMul) -> InstanceMul {
+        self.instance_mul.unwrap_or(global)
+    }
+
+    pub fn is_audible(&self, any_solo: bool) -> bool {
+        !self.mute && (self.solo || !any_solo)
+    }
+
+    pub fn is_visible(&self, any_solo: bool) -> bool {
+        !self.hidden && self.is_audible(any_solo)
+    }
+}
+
+/// Gain of an audio stem, only present when rendering stems.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StemState {
+    pub name: String,
+    pub gain: f32,
+}
+
+impl StemState {
+    pub fn init(name: String) -> Self {
+        Self { name, gain: 1.0 }
+    }
+}
+
+impl UiState {
+    pub fn any_solo(&self) -> bool {
+        self.streams.iter().any(|stream| stream.solo)
+    }
+
+    pub fn select_stream(&mut self, offset: isize) {
+        if self.streams.is_empty() {
+            return;
+        }
+        let n = self.streams.len() as isize;
+        self.selected_stream = (self.selected_stream as isize + offset).rem_euclid(n) as usize;
+    }
+
+    pub fn selected_stream_mut(&mut self) -> Option<&mut StreamState> {
+        self.streams.get_mut(self.selected_stream)
+    }
 }
 
 #[derive(Copy, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -50,6 +196,41 @@ impl Default for InstanceMul {
     }
 }
 
+/// How strongly the camera reacts to the audio, 0 turns a reaction off.
+#[derive(Copy, Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CameraReactivity {
+    /// Shakes the camera on beats.
+    pub shake: f32,
+    /// Narrows the field of view on onsets.
+    pub fov_pulse: f32,
+    /// Moves the camera forward while the music stays loud.
+    pub dolly: f32,
+}
+
+/// How the camera projects the scene. Angles are in degrees.
+#[derive(Copy, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProjectionConfig {
+    /// Vertical field of view of the perspective projection.
+    pub fov: f32,
+    pub znear: f32,
+    pub zfar: f32,
+    /// Parallel projection, showing `height` world units vertically.
+    pub orthographic: bool,
+    pub height: f32,
+}
+
+impl Default for ProjectionConfig {
+    fn default() -> Self {
+        Self {
+            fov: 70.0,
+            znear: 0.1,
+            zfar: 30_000.0,
+            orthographic: false,
+            height: 500.0,
+        }
+    }
+}
+
 /// Wraps many demo/test apps into one.
 pub struct WrapApp {
     selected_anchor: String,
@@ -57,10 +238,10 @@ pub struct WrapApp {
 }
 
 impl WrapApp {
-    pub fn init(state: Arc<Mutex<UiState>>, n_camera: usize) -> Self {
+    pub fn init(state: Arc<Mutex<UiState>>) -> Self {
         WrapApp {
             selected_anchor: "".to_string(),
-            apps: Apps::init(state, n_camera),
+            apps: Apps::init(state),
         }
     }
 }
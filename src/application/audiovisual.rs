@@ -0,0 +1,126 @@
+use weresocool::error::Error;
+use weresocool::generation::parsed_to_render::AudioVisual;
+use weresocool::generation::{RenderReturn, RenderType, Stem};
+use weresocool::interpretable::{InputType, Interpretable};
+
+use super::cache::{CachedRender, RenderCache};
+use crate::audio::analysis::{AudioFeatures, HOP};
+use crate::data::DataConfig;
+use crate::op_stream::OpStream;
+
+/// Everything needed to visualize a piece, independent of where the op
+/// streams and audio came from.
+pub struct AudioVisualStreams {
+    pub op_streams: Vec<OpStream>,
+    /// `None` when there is nothing to play, e.g. for data without audio.
+    pub audio: Option<Vec<u8>>,
+    pub stems: Option<Vec<Stem>>,
+    pub length: f32,
+}
+
+impl AudioVisualStreams {
+    pub fn from_socool(
+        filename: &str,
+        stems: bool,
+        render_cache: Option<&str>,
+    ) -> Result<Self, Error> {
+        let render = || {
+            get_audiovisual_data(filename).map(|av| CachedRender {
+                audio: av.audio,
+                visual: av.visual,
+                length: av.length,
+            })
+        };
+        let av = match render_cache {
+            Some(directory) => RenderCache::init(directory).get_or_render(filename, render)?,
+            None => render()?,
+        };
+        Ok(Self {
+            op_streams: OpStream::from_ops(&av.visual, av.length),
+            audio: Some(av.audio),
+            // Stems always render, only the mixed render is cached.
+            stems: if stems {
+                Some(get_stems(filename)?)
+            } else {
+                None
+            },
+            length: av.length,
+        })
+    }
+
+    pub fn from_json(filename: &str, audio: Option<&str>) -> Result<Self, Error> {
+        let op_streams = OpStream::from_json(filename)?;
+        let length = op_streams
+            .iter()
+            .map(|op_stream| op_stream.length)
+            .fold(0.0, f32::max);
+        let audio = match audio {
+            Some(path) => Some(std::fs::read(path)?),
+            None => None,
+        };
+
+        Ok(Self {
+            op_streams,
+            audio,
+            stems: None,
+            length,
+        })
+    }
+
+    pub fn from_data(data: &DataConfig) -> Result<Self, Error> {
+        let op_streams = data.op_streams()?;
+        let length = op_streams
+            .iter()
+            .map(|op_stream| op_stream.length)
+            .fold(0.0, f32::max);
+        let audio = match &data.audio {
+            Some(path) => Some(std::fs::read(path)?),
+            None => None,
+        };
+
+        Ok(Self {
+            op_streams,
+            audio,
+            stems: None,
+            length,
+        })
+    }
+
+    /// What the camera reacts to: the audio if it decodes, otherwise the
+    /// density of the ops.
+    pub fn features(&self) -> AudioFeatures {
+        self.audio
+            .as_ref()
+            .and_then(|audio| match AudioFeatures::from_audio(audio) {
+                Ok(features) => Some(features),
+                Err(e) => {
+                    println!("analysing ops instead of audio: {:?}", e);
+                    None
+                }
+            })
+            .unwrap_or_else(|| AudioFeatures::from_op_streams(&self.op_streams, self.length, HOP))
+    }
+}
+
+fn get_stems(filename: &str) -> Result<Vec<Stem>, Error> {
+    if let RenderReturn::Stems(stems) =
+        InputType::Filename(&filename).make(RenderType::Stems, None)?
+    {
+        Ok(stems)
+    } else {
+        Err(Error::with_msg(format!(
+            "Error rendering stems {}",
+            filename
+        )))
+    }
+}
+
+fn get_audiovisual_data(filename: &str) -> Result<AudioVisual, Error> {
+    if let RenderReturn::AudioVisual(av) =
+        InputType::Filename(&filename).make(RenderType::AudioVisual, None)?
+    {
+        Ok(av)
+    } else {
+        Err(Error::with_msg(format!("Error rendering {}", filename)))
+    }
+}
@@ -0,0 +1,179 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeSet;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use weresocool::error::Error;
+
+use crate::op_stream::{Op4D, OpStream};
+
+/// A weresocool render stored on disk, keyed by a hash of the `.socool` file
+/// and every file it references, so that data-heavy pieces only render once.
+///
+/// ```text
+/// {directory}/{key}/audio.wav
+/// {directory}/{key}/visual.json
+/// ```
+///
+/// Stems, rendered for `--stems`, aren't cached.
+pub struct RenderCache {
+    pub directory: PathBuf,
+}
+
+pub struct CachedRender {
+    pub audio: Vec<u8>,
+    pub visual: Vec<Op4D>,
+    pub length: f32,
+}
+
+impl RenderCache {
+    pub fn init(directory: &str) -> Self {
+        Self {
+            directory: PathBuf::from(directory),
+        }
+    }
+
+    pub fn get_or_render<F>(&self, filename: &str, render: F) -> Result<CachedRender, Error>
+    where
+        F: FnOnce() -> Result<CachedRender, Error>,
+    {
+        let key = cache_key(filename)?;
+        if let Some(cached) = self.load(&key)? {
+            println!("Using cached render {} for {}", key, filename);
+            return Ok(cached);
+        }
+
+        let rendered = render()?;
+        self.store(&key, &rendered)?;
+        Ok(rendered)
+    }
+
+    fn load(&self, key: &str) -> Result<Option<CachedRender>, Error> {
+        let dir = self.directory.join(key);
+        let audio_path = dir.join("audio.wav");
+        let visual_path = dir.join("visual.json");
+        if !audio_path.is_file() || !visual_path.is_file() {
+            return Ok(None);
+        }
+
+        let audio = std::fs::read(audio_path)?;
+        let visual: OpStream = serde_json::from_str(&std::fs::read_to_string(&visual_path)?)
+            .map_err(|e| {
+                Error::with_msg(format!("Unable to parse {}: {}", visual_path.display(), e))
+            })?;
+
+        Ok(Some(CachedRender {
+            audio,
+            visual: visual.ops,
+            length: visual.length,
+        }))
+    }
+
+    /// Writes to a temporary directory that is renamed into place, so an
+    /// interrupted store never leaves a partial render to be loaded.
+    fn store(&self, key: &str, render: &CachedRender) -> Result<(), Error> {
+        let dir = self.directory.join(key);
+        let tmp = self
+            .directory
+            .join(format!("{}.tmp-{}", key, std::process::id()));
+        std::fs::create_dir_all(&tmp)?;
+        let visual = OpStream {
+            ops: render.visual.to_owned(),
+            length: render.length,
+            names: vec![],
+        };
+        let serialized = serde_json::to_string(&visual)
+            .map_err(|e| Error::with_msg(format!("Unable to serialize render: {}", e)))?;
+
+        std::fs::write(tmp.join("visual.json"), serialized)?;
+        std::fs::write(tmp.join("audio.wav"), &render.audio)?;
+        if dir.exists() {
+            std::fs::remove_dir_all(&dir)?;
+        }
+        if let Err(e) = std::fs::rename(&tmp, &dir) {
+            std::fs::remove_dir_all(&tmp).ok();
+            return Err(Error::with_msg(format!(
+                "Unable to cache render in {}: {}",
+                dir.display(),
+                e
+            )));
+        }
+        println!("Render cached in {}", dir.display());
+        Ok(())
+    }
+}
+
+/// Hashes the contents of `filename` and of every file it references, and
+/// the kintaro and weresocool versions. The hasher is only stable for a given
+/// toolchain, so upgrading rust invalidates the cache rather than reusing a
+/// stale render.
+pub fn cache_key(filename: &str) -> Result<String, Error> {
+    let mut hasher = DefaultHasher::new();
+    env!("CARGO_PKG_VERSION").hash(&mut hasher);
+    env!("WERESOCOOL_VERSION").hash(&mut hasher);
+    for path in referenced_files(Path::new(filename))? {
+        path.hash(&mut hasher);
+        std::fs::read(&path)?.hash(&mut hasher);
+    }
+    Ok(format!("{:016x}", hasher.finish()))
+}
+
+/// The `.socool` file itself, followed by every existing file named in it,
+/// e.g. `csv ./data/meg_0111.csv` or `import ./other.socool`. Referenced
+/// `.socool` files are followed recursively.
+pub fn referenced_files(path: &Path) -> Result<BTreeSet<PathBuf>, Error> {
+    let mut files = BTreeSet::new();
+    collect_referenced_files(path, &mut files)?;
+    Ok(files)
+}
+
+fn collect_referenced_files(path: &Path, files: &mut BTreeSet<PathBuf>) -> Result<(), Error> {
+    if !files.insert(path.to_path_buf()) {
+        return Ok(());
+    }
+    let contents = std::fs::read_to_string(path)?;
+    let parent = path.parent().unwrap_or_else(|| Path::new("."));
+
+    for token in contents
+        .split(|c: char| c.is_whitespace() || "\"'(),[]{}|".contains(c))
+        .filter(|token| token.contains('.') && !token.is_empty())
+    {
+        let candidates = [PathBuf::from(token), parent.join(token)];
+        if let Some(referenced) = candidates.iter().find(|candidate| candidate.is_file()) {
+            if referenced.extension().map_or(false, |ext| ext == "socool") {
+                collect_referenced_files(referenced, files)?;
+            } else {
+                files.insert(referenced.to_owned());
+            }
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_cache_key_and_referenced_files() {
+    let dir = std::env::temp_dir().join(format!("kintaro-cache-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let main = dir.join("main.socool");
+    let other = dir.join("other.socool");
+    let csv = dir.join("values.csv");
+    std::fs::write(&main, "import other.socool\n{ f: 220, l: 1, g: 1, p: 0 }\n").unwrap();
+    std::fs::write(&other, "csv(values.csv)\nmissing.csv\n").unwrap();
+    std::fs::write(&csv, "1.0,2.0\n").unwrap();
+
+    let files = referenced_files(&main).unwrap();
+    assert_eq!(
+        files,
+        vec![main.clone(), other.clone(), dir.join("values.csv")]
+            .into_iter()
+            .collect()
+    );
+
+    let filename = main.to_str().unwrap();
+    let key = cache_key(filename).unwrap();
+    assert_eq!(key, cache_key(filename).unwrap());
+    // Editing a referenced file invalidates the render.
+    std::fs::write(&csv, "1.0,3.0\n").unwrap();
+    assert_ne!(key, cache_key(filename).unwrap());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -1,14 +1,28 @@
-use crate::config::Config;
+mod audiovisual;
+pub mod cache;
+
+pub use self::audiovisual::AudioVisualStreams;
+use crate::audio::analysis::AudioFeatures;
+use crate::audio::Tracks;
+use crate::camera::path::CameraPath;
+use crate::config::{Config, Source};
+use crate::midi::{MidiInput, MidiMapping};
+use crate::op_stream::OpStream;
+use crate::osc::{OscConfig, OscOutput, OscServer};
 use crate::print::PrintState;
+use crate::realtime::bindings::KeyBindings;
+use crate::realtime::reload::Reloader;
 use crate::realtime::render::ExampleRepaintSignal;
 use crate::realtime::RealTimeState;
+use crate::remote::RemoteServer;
+use crate::shared::Fallback;
 use cradle::prelude::*;
 use std::io::Write;
+use std::path::Path;
 use std::str::FromStr;
+use std::time::Duration;
 use weresocool::error::Error;
-use weresocool::generation::parsed_to_render::AudioVisual;
-use weresocool::generation::{RenderReturn, RenderType};
-use weresocool::interpretable::{InputType, Interpretable};
+use weresocool::generation::Stem;
 use winit::dpi::PhysicalSize;
 #[allow(unused_imports)]
 use winit::window::Fullscreen;
@@ -16,58 +30,202 @@ use winit::{event::*, event_loop::ControlFlow, window::WindowBuilder};
 
 use futures::executor::block_on;
 
-pub fn run(filename: &str, config: Config) -> Result<(), Error> {
-    println!("preparing for audiovisualization: {}", &filename);
-    let av = get_audiovisual_data(filename)?;
-    let print_it = std::env::args()
-        .into_iter()
-        .any(|arg| if arg == "--print" { true } else { false });
+pub fn run(filename: &str, mut config: Config) -> Result<(), Error> {
+    let print_it = has_arg("--print");
+    config.stems = config.stems || has_arg("--stems");
+    if has_arg("--json") {
+        config.source = Source::Json {
+            audio: arg_value("--audio"),
+        };
+    }
+    if let Some(seed) = arg_value("--seed") {
+        config.seed = seed
+            .parse()
+            .map_err(|_| Error::with_msg(format!("invalid --seed: {}", seed)))?;
+    }
+    println!("seed: {}", config.seed);
+    config.cameras = config.cameras.to_owned().load_for(filename)?;
+    config.camera_path = CameraPath::load_for(filename)?;
+    config.key_bindings = config
+        .key_bindings
+        .to_owned()
+        .load(Path::new(KeyBindings::PATH))?;
+    config.midi = config.midi.to_owned().load(Path::new(MidiMapping::PATH))?;
+    if has_arg("--osc") {
+        config
+            .osc
+            .listen
+            .get_or_insert_with(|| OscConfig::LISTEN.into());
+    }
+    if let Some(target) = arg_value("--osc-send") {
+        config.osc.send = Some(target);
+    }
+    if has_arg("--remote") {
+        config
+            .remote
+            .get_or_insert_with(|| RemoteServer::ADDR.into());
+    }
+    if let Some(addr) = arg_value("--remote-addr") {
+        config.remote = Some(addr);
+    }
+    if let Some(size) = arg_value("--screenshot-size") {
+        config.screenshot_size = Some(parse_size(&size).ok_or_else(|| {
+            Error::with_msg(format!("invalid --screenshot-size, expected WxH: {}", size))
+        })?);
+    }
+    config.device = config.device.to_owned().with_env()?;
+    if has_arg("--software") {
+        config.device.fallback = Fallback::Only;
+    }
+    let av = match &config.source {
+        Source::Socool => {
+            println!("preparing for audiovisualization: {}", &filename);
+            let render_cache = if has_arg("--no-cache") {
+                None
+            } else {
+                config.render_cache.as_deref()
+            };
+            AudioVisualStreams::from_socool(filename, config.stems, render_cache)?
+        }
+        Source::Json { audio } => {
+            println!(
+                "loading op streams: {}",
+                OpStream::json_path(filename).display()
+            );
+            AudioVisualStreams::from_json(filename, audio.as_deref())?
+        }
+        Source::Data(data) => {
+            println!("preparing for data visualization: {:?}", &data.paths);
+            AudioVisualStreams::from_data(data)?
+        }
+    };
+    if has_arg("--export-json") {
+        OpStream::to_json(&av.op_streams, filename)?;
+        if let Some(audio) = &av.audio {
+            write_audio_to_file(audio, Path::new(filename).with_extension("wav"))?;
+        }
+    }
 
     if print_it {
         println!("****PRINTING****");
-        let n_frames = (av.length * 40.0).floor() as usize + 100;
-        print(config, &av, n_frames)?;
-        write_audio_to_file(
-            &av.audio.as_slice(),
-            std::path::PathBuf::from_str("kintaro.wav")
-                .expect("unable to create pathbuf for kintaro.wav"),
-        );
+        let length = av.length;
+        let features = av.features();
+        let AudioVisualStreams {
+            op_streams,
+            audio,
+            stems,
+            ..
+        } = av;
+        print(config, op_streams, features, length)?;
+        if let Some(stems) = &stems {
+            write_stems_to_files(stems, "stems")?;
+        }
+
+        if let Some(audio) = &audio {
+            write_audio_to_file(
+                &audio.as_slice(),
+                std::path::PathBuf::from_str("kintaro.wav")
+                    .expect("unable to create pathbuf for kintaro.wav"),
+            )?;
+            let command_join_audio_and_video = "ffmpeg -framerate 40 -pattern_type glob -i out/*.png -i kintaro.wav -c:a copy -shortest -c:v libx264 -r 40 -pix_fmt yuv420p out.mov";
 
-        let command_join_audio_and_video = "ffmpeg -framerate 40 -pattern_type glob -i out/*.png -i kintaro.wav -c:a copy -shortest -c:v libx264 -r 40 -pix_fmt yuv420p out.mov";
+            run!(Stdin("yes"), %command_join_audio_and_video);
+        } else {
+            let command_join_video = "ffmpeg -framerate 40 -pattern_type glob -i out/*.png -c:v libx264 -r 40 -pix_fmt yuv420p out.mov";
 
-        run!(Stdin("yes"), %command_join_audio_and_video);
+            run!(Stdin("yes"), %command_join_video);
+        }
     } else {
         println!("****REALTIME****");
-        realtime(config, &av)?;
+        let reload = match config.source {
+            Source::Socool if config.live_reload => Some(filename.to_string()),
+            _ => None,
+        };
+        realtime(config, av, reload)?;
     }
     Ok(())
 }
 
-pub fn write_audio_to_file(audio: &[u8], filename: std::path::PathBuf) {
-    let mut file = std::fs::File::create(filename.clone()).unwrap();
-    file.write_all(audio).unwrap();
+pub fn write_audio_to_file(audio: &[u8], filename: std::path::PathBuf) -> Result<(), Error> {
+    let mut file = std::fs::File::create(filename.clone())?;
+    file.write_all(audio)?;
     println!("Audio file written: {}", filename.display().to_string());
+    Ok(())
 }
 
-fn get_audiovisual_data(filename: &str) -> Result<AudioVisual, Error> {
-    if let RenderReturn::AudioVisual(av) =
-        InputType::Filename(&filename).make(RenderType::AudioVisual, None)?
-    {
-        Ok(av)
+/// Connects to the port matching `--midi-port`, or the first one, when run
+/// with `--midi`.
+#[cfg(feature = "midi")]
+fn midi_input() -> Result<Option<MidiInput>, Error> {
+    if has_arg("--midi") {
+        MidiInput::connect(arg_value("--midi-port").as_deref()).map(Some)
     } else {
-        Err(Error::with_msg(format!("Error rendering {}", filename)))
+        Ok(None)
     }
 }
 
-fn print(mut config: Config, av: &AudioVisual, n_frames: usize) -> Result<(), Error> {
-    let mut state = block_on(PrintState::init(&mut config, av))?;
+#[cfg(not(feature = "midi"))]
+fn midi_input() -> Result<Option<MidiInput>, Error> {
+    if has_arg("--midi") {
+        println!("midi input needs kintaro to be built with the midi feature");
+    }
+    Ok(None)
+}
+
+/// A `WxH` size, e.g. `3840x2160`.
+fn parse_size(value: &str) -> Option<(u32, u32)> {
+    let (width, height) = value.split_once('x')?;
+    Some((width.trim().parse().ok()?, height.trim().parse().ok()?))
+}
+
+fn has_arg(name: &str) -> bool {
+    std::env::args().into_iter().any(|arg| arg == name)
+}
+
+fn arg_value(name: &str) -> Option<String> {
+    std::env::args()
+        .into_iter()
+        .skip_while(|arg| arg != name)
+        .nth(1)
+}
+
+pub fn write_stems_to_files(stems: &[Stem], directory: &str) -> Result<(), Error> {
+    std::fs::create_dir_all(directory)?;
+    for stem in stems.iter() {
+        write_audio_to_file(
+            &stem.audio,
+            std::path::Path::new(directory).join(format!("{}.wav", stem.name)),
+        )?;
+    }
+    Ok(())
+}
+
+fn print(
+    mut config: Config,
+    op_streams: Vec<OpStream>,
+    features: AudioFeatures,
+    length: f32,
+) -> Result<(), Error> {
+    let mut state = block_on(PrintState::init(&mut config, op_streams, features))?;
+    // The streams' lengths are those after their transforms.
+    let length = state
+        .composition
+        .renderpasses
+        .iter()
+        .map(|renderpass| renderpass.op_stream.length)
+        .fold(length, f32::max);
+    let n_frames = (length * 40.0).floor() as usize + 100;
     for i in 0..n_frames {
         block_on(state.render()).expect(format!("Unable to render frame: {}", i).as_str());
     }
     Ok(())
 }
 
-fn realtime(mut config: Config, av: &AudioVisual) -> Result<(), Error> {
+fn realtime(
+    mut config: Config,
+    av: AudioVisualStreams,
+    reload: Option<String>,
+) -> Result<(), Error> {
     env_logger::init();
     let title = env!("CARGO_PKG_NAME");
     let event_loop = winit::event_loop::EventLoop::with_user_event();
@@ -87,14 +245,29 @@ fn realtime(mut config: Config, av: &AudioVisual) -> Result<(), Error> {
         event_loop.create_proxy(),
     )));
 
-    let (mut _stream, stream_handle) = crate::audio::play_audio(&config, &av.audio);
-    let mut state = RealTimeState::init(
-        &window,
-        &mut config,
-        repaint_signal.clone(),
-        stream_handle,
-        &av,
-    )?;
+    let tracks = Tracks::from_audiovisual(&config, &av, Duration::ZERO)?;
+    let mut state = RealTimeState::init(&window, &mut config, repaint_signal.clone(), tracks, av)?;
+    if let Some(filename) = reload {
+        state.reloader = Some(Reloader::watch(
+            filename,
+            config.stems,
+            config.render_cache.to_owned(),
+            state.gui.state.clone(),
+        ));
+    }
+    state.midi = midi_input()?;
+    if let Some(addr) = &config.osc.listen {
+        OscServer::listen(addr, state.gui.state.clone())?;
+    }
+    if let Some(addr) = &config.remote {
+        RemoteServer::listen(addr, state.gui.state.clone())?;
+    }
+    state.composition.osc = config
+        .osc
+        .send
+        .as_deref()
+        .map(OscOutput::connect)
+        .transpose()?;
     state.play();
 
     event_loop.run(move |event, _, control_flow| {
@@ -112,16 +285,11 @@ fn realtime(mut config: Config, av: &AudioVisual) -> Result<(), Error> {
                 // dbg!(event);
                 match event {
                     WindowEvent::CloseRequested => *control_flow = ControlFlow::Exit,
-                    WindowEvent::KeyboardInput { input, .. } => match input {
-                        KeyboardInput {
-                            state: ElementState::Pressed,
-                            virtual_keycode: Some(VirtualKeyCode::Escape),
-                            ..
-                        } => {
+                    WindowEvent::KeyboardInput { .. } => {
+                        if state.keyboard_input(event) {
                             *control_flow = ControlFlow::Exit;
                         }
-                        _ => state.keyboard_input(event),
-                    },
+                    }
                     WindowEvent::Resized(physical_size) => {
                         state.resize(*physical_size);
                     }
@@ -154,3 +322,10 @@ fn realtime(mut config: Config, av: &AudioVisual) -> Result<(), Error> {
     #[allow(unreachable_code)]
     Ok(())
 }
+
+#[test]
+fn test_parse_size() {
+    assert_eq!(parse_size("3840x2160"), Some((3840, 2160)));
+    assert_eq!(parse_size("3840"), None);
+    assert_eq!(parse_size("-1x100"), None);
+}
@@ -0,0 +1,133 @@
+use rodio::{Decoder, Source};
+use std::io::Cursor;
+use weresocool::error::Error;
+
+use crate::op_stream::{EventType, OpStream};
+
+/// Seconds between analysis frames.
+pub const HOP: f32 = 0.01;
+
+/// The loudness of a piece over time, analysed once up front so realtime and
+/// print see the same values at the same time.
+#[derive(Clone, Debug, Default)]
+pub struct AudioFeatures {
+    pub hop: f32,
+    /// Loudness in 0..1.
+    pub energy: Vec<f32>,
+    /// How suddenly the loudness rose, in 0..1.
+    pub onset: Vec<f32>,
+}
+
+/// The features at one point in time.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct AudioFrame {
+    pub energy: f32,
+    pub onset: f32,
+}
+
+impl AudioFeatures {
+    /// Analyses encoded audio, e.g. the wav rendered by weresocool.
+    pub fn from_audio(audio: &[u8]) -> Result<Self, Error> {
+        let decoder = Decoder::new(Cursor::new(audio.to_owned()))
+            .map_err(|e| Error::with_msg(format!("Unable to decode audio: {}", e)))?;
+        let channels = decoder.channels().max(1) as usize;
+        let sample_rate = decoder.sample_rate();
+        let interleaved: Vec<f32> = decoder.convert_samples::<f32>().collect();
+        let mono: Vec<f32> = interleaved
+            .chunks(channels)
+            .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+            .collect();
+        Ok(Self::from_samples(&mono, sample_rate, HOP))
+    }
+
+    /// Analyses mono samples, the rms of each hop being its energy.
+    pub fn from_samples(samples: &[f32], sample_rate: u32, hop: f32) -> Self {
+        let hop_len = ((sample_rate as f32 * hop) as usize).max(1);
+        let rms = samples
+            .chunks(hop_len)
+            .map(|chunk| (chunk.iter().map(|s| s * s).sum::<f32>() / chunk.len() as f32).sqrt())
+            .collect();
+        Self::from_envelope(rms, hop)
+    }
+
+    /// Approximates the features from the ops when there is no audio: the
+    /// number of sounding notes is the energy, notes starting are onsets.
+    pub fn from_op_streams(op_streams: &[OpStream], length: f32, hop: f32) -> Self {
+        let n = (length / hop).ceil() as usize + 1;
+        let mut density = vec![0.0; n];
+        op_streams
+            .iter()
+            .flat_map(|op_stream| op_stream.ops.iter())
+            .filter(|op| matches!(op.event_type, EventType::On))
+            .for_each(|op| {
+                let start = (op.t as f32 / hop) as usize;
+                let end = ((op.t + op.l) as f32 / hop).ceil() as usize;
+                for frame in density.iter_mut().take(end.max(start + 1)).skip(start) {
+                    *frame += 1.0;
+                }
+            });
+        Self::from_envelope(density, hop)
+    }
+
+    fn from_envelope(envelope: Vec<f32>, hop: f32) -> Self {
+        let energy = normalize(envelope);
+        // The rise over the average of the previous 50ms.
+        let window = ((0.05 / hop) as usize).max(1);
+        let onset = (0..energy.len())
+            .map(|idx| {
+                let previous = &energy[idx.saturating_sub(window)..idx];
+                if previous.is_empty() {
+                    return energy[idx];
+                }
+                let mean = previous.iter().sum::<f32>() / previous.len() as f32;
+                (energy[idx] - mean).max(0.0)
+            })
+            .collect();
+        Self {
+            hop,
+            energy,
+            onset: normalize(onset),
+        }
+    }
+
+    pub fn at(&self, t: f32) -> AudioFrame {
+        if self.energy.is_empty() || t < 0.0 {
+            return AudioFrame::default();
+        }
+        let idx = ((t / self.hop) as usize).min(self.energy.len() - 1);
+        AudioFrame {
+            energy: self.energy[idx],
+            onset: self.onset[idx],
+        }
+    }
+}
+
+fn normalize(values: Vec<f32>) -> Vec<f32> {
+    let max = values.iter().cloned().fold(0.0, f32::max);
+    if max <= 0.0 {
+        return values;
+    }
+    values.into_iter().map(|value| value / max).collect()
+}
+
+#[test]
+fn test_onsets_follow_loudness() {
+    let sample_rate = 1000;
+    // Silence, then a tone of whole periods per hop from 0.5s.
+    let samples: Vec<f32> = (0..sample_rate)
+        .map(|idx| {
+            if idx < sample_rate / 2 {
+                0.0
+            } else {
+                (idx as f32 * std::f32::consts::PI / 5.0).sin()
+            }
+        })
+        .collect();
+    let features = AudioFeatures::from_samples(&samples, sample_rate as u32, HOP);
+
+    assert_eq!(features.at(0.2), AudioFrame::default());
+    assert!(features.at(0.505).onset > 0.9);
+    assert!(features.at(0.9).energy > 0.9);
+    assert!(features.at(0.9).onset < 0.1);
+    assert_eq!(features.at(10.0), features.at(0.99));
+}
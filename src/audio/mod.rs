@@ -1,6 +1,106 @@
-use rodio::OutputStream;
+pub mod analysis;
+
+use kintaro_egui_lib::UiState;
+use rodio::{Decoder, OutputStream, OutputStreamHandle, Source};
 use std::fs::File;
 use std::io::{BufReader, Cursor};
+use std::time::Duration;
+use weresocool::error::Error;
+use weresocool::generation::Stem;
+
+use crate::application::AudioVisualStreams;
+use crate::shared::RenderPassInput;
+
+/// A sink playing either the full mix or a single named stem.
+pub struct Track {
+    /// `None` for the full mix.
+    pub name: Option<String>,
+    pub sink: rodio::Sink,
+    /// Indices of the renderpasses drawing this track's voice.
+    pub streams: Vec<usize>,
+}
+
+/// All of the sinks for a composition. They are started and paused together
+/// and mixed by rodio, so the gain of each stem can change in realtime.
+pub struct Tracks {
+    pub tracks: Vec<Track>,
+    /// Keeps the output device open for as long as the tracks play.
+    pub stream: Option<OutputStream>,
+}
+
+impl Tracks {
+    pub fn silent() -> Self {
+        Self {
+            tracks: vec![],
+            stream: None,
+        }
+    }
+
+    /// Plays the stems if there are any, otherwise the mix, starting at
+    /// `offset` into the piece.
+    pub fn from_audiovisual(
+        config: &crate::config::Config,
+        av: &AudioVisualStreams,
+        offset: Duration,
+    ) -> Result<Self, Error> {
+        match (&av.stems, &av.audio) {
+            (Some(stems), _) => play_stems(config, stems, offset),
+            (None, Some(audio)) => play_audio(config, audio, offset),
+            (None, None) => Ok(Tracks::silent()),
+        }
+    }
+
+    pub fn play(&self) {
+        self.tracks.iter().for_each(|track| track.sink.play());
+    }
+
+    pub fn pause(&self) {
+        self.tracks.iter().for_each(|track| track.sink.pause());
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.tracks.iter().all(|track| track.sink.is_paused())
+    }
+
+    /// Links each stem to the renderpasses whose op stream carries its name,
+    /// so muting or soloing a stream also silences its audio.
+    pub fn link_streams(&mut self, renderpasses: &[RenderPassInput]) {
+        self.tracks.iter_mut().for_each(|track| {
+            if let Some(name) = &track.name {
+                track.streams = renderpasses
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, renderpass)| renderpass.op_stream.names.contains(name))
+                    .map(|(idx, _)| idx)
+                    .collect();
+            }
+        });
+    }
+
+    pub fn set_volume(&self, state: &UiState) {
+        let any_solo = state.any_solo();
+        self.tracks.iter().for_each(|track| {
+            let gain = track.name.as_ref().map_or(1.0, |name| {
+                state
+                    .stems
+                    .iter()
+                    .find(|stem| &stem.name == name)
+                    .map_or(1.0, |stem| stem.gain)
+            });
+            let audible = track.streams.is_empty()
+                || track.streams.iter().any(|idx| {
+                    state
+                        .streams
+                        .get(*idx)
+                        .map_or(true, |stream| stream.is_audible(any_solo))
+                });
+
+            track
+                .sink
+                .set_volume(if audible { state.volume * gain } else { 0.0 });
+        });
+    }
+}
 
 #[allow(unused)]
 pub fn play_audio_file(config: &crate::config::Config) -> (OutputStream, rodio::Sink) {
@@ -14,12 +114,60 @@ pub fn play_audio_file(config: &crate::config::Config) -> (OutputStream, rodio::
     (stream, stream_handle)
 }
 
-pub fn play_audio(config: &crate::config::Config, audio: &Vec<u8>) -> (OutputStream, rodio::Sink) {
-    let (stream, stream_handle) = OutputStream::try_default().unwrap();
-    let stream_handle = stream_handle
-        .play_once(Cursor::new(audio.to_owned()))
-        .unwrap();
-    stream_handle.pause();
-    stream_handle.set_volume(config.volume);
-    (stream, stream_handle)
+pub fn play_audio(
+    config: &crate::config::Config,
+    audio: &Vec<u8>,
+    offset: Duration,
+) -> Result<Tracks, Error> {
+    let (stream, stream_handle) = OutputStream::try_default()
+        .map_err(|e| Error::with_msg(format!("Unable to open audio output: {}", e)))?;
+    let sink = make_sink(config, &stream_handle, audio, offset)?;
+    Ok(Tracks {
+        tracks: vec![Track {
+            name: None,
+            sink,
+            streams: vec![],
+        }],
+        stream: Some(stream),
+    })
+}
+
+pub fn play_stems(
+    config: &crate::config::Config,
+    stems: &[Stem],
+    offset: Duration,
+) -> Result<Tracks, Error> {
+    let (stream, stream_handle) = OutputStream::try_default()
+        .map_err(|e| Error::with_msg(format!("Unable to open audio output: {}", e)))?;
+    let tracks = stems
+        .iter()
+        .map(|stem| {
+            Ok(Track {
+                name: Some(stem.name.to_owned()),
+                sink: make_sink(config, &stream_handle, &stem.audio, offset)?,
+                streams: vec![],
+            })
+        })
+        .collect::<Result<Vec<Track>, Error>>()?;
+    Ok(Tracks {
+        tracks,
+        stream: Some(stream),
+    })
+}
+
+/// A paused sink, skipped forward to `offset`.
+fn make_sink(
+    config: &crate::config::Config,
+    stream_handle: &OutputStreamHandle,
+    audio: &Vec<u8>,
+    offset: Duration,
+) -> Result<rodio::Sink, Error> {
+    let sink = rodio::Sink::try_new(stream_handle)
+        .map_err(|e| Error::with_msg(format!("Unable to create audio sink: {}", e)))?;
+    let source = Decoder::new(Cursor::new(audio.to_owned()))
+        .map_err(|e| Error::with_msg(format!("Unable to decode audio: {}", e)))?;
+    sink.append(source.skip_duration(offset));
+    sink.pause();
+    sink.set_volume(config.volume);
+    Ok(sink)
 }
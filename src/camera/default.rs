@@ -1,4 +1,6 @@
+use crate::camera::mode::CameraMode;
 use crate::config::CameraConfig;
+use kintaro_egui_lib::ProjectionConfig;
 
 pub fn default_cameras(
     mut vec_saved_cameras: Vec<CameraConfig>,
@@ -14,26 +16,36 @@ pub fn default_cameras(
             position: (310.0 + offset.0, 83.0 + offset.1, 77.0 + offset.2),
             yaw: -142.0,
             pitch: 1.77,
+            mode: CameraMode::Fly,
+            projection: ProjectionConfig::default(),
         },
         CameraConfig {
             position: (-218.0 + offset.0, -40.0 + offset.1, -89.0 + offset.2),
             yaw: -4.0,
             pitch: 31.8,
+            mode: CameraMode::Fly,
+            projection: ProjectionConfig::default(),
         },
         CameraConfig {
             position: (-116.2 + offset.0, 36.0 + offset.1, 106.0 + offset.2),
             yaw: -56.11,
             pitch: 5.917,
+            mode: CameraMode::Fly,
+            projection: ProjectionConfig::default(),
         },
         CameraConfig {
             position: (0.0 + offset.0, 80.0 + offset.1, 400.0 + offset.2),
             yaw: -90.0,
             pitch: 11.0,
+            mode: CameraMode::Fly,
+            projection: ProjectionConfig::default(),
         },
         CameraConfig {
             position: (0.0 + offset.0, 670.0 + offset.1, -226.0 + offset.2),
             yaw: 0.0,
             pitch: -90.0,
+            mode: CameraMode::Fly,
+            projection: ProjectionConfig::default(),
         },
     ]);
     vec_saved_cameras
@@ -1,11 +1,22 @@
 use cgmath::*;
 pub mod default;
+pub mod mode;
+pub mod path;
+pub mod presets;
+pub mod reactive;
+pub mod transition;
 use serde::{Deserialize, Serialize};
 use std::f32::consts::FRAC_PI_2;
 use winit::dpi::PhysicalPosition;
 use winit::event::*;
 
+use self::mode::{look_at_angles, orbit_position, CameraMode};
+use self::path::Keyframe;
+use self::reactive::{CameraOffset, Reactive};
+use self::transition::{Transition, TransitionConfig};
+use crate::audio::analysis::AudioFrame;
 use crate::config::{CameraConfig, Config};
+use kintaro_egui_lib::ProjectionConfig;
 
 #[rustfmt::skip]
 pub const OPENGL_TO_WGPU_MATRIX: cgmath::Matrix4<f32> = cgmath::Matrix4::new(
@@ -25,26 +36,142 @@ pub struct Camera {
     pub projection: Projection,
     pub controller: CameraController,
     pub index: usize,
+    /// An animation to a preset in progress.
+    #[serde(skip)]
+    pub transition: Option<Transition>,
+    pub mode: CameraMode,
+    /// The point `CameraMode::LookAt` faces, updated by the composition.
+    #[serde(skip)]
+    pub look_at: Option<Point3<f32>>,
+    /// The audio at the current time, updated by the composition.
+    #[serde(skip)]
+    pub audio: AudioFrame,
+    #[serde(skip)]
+    pub reactive: Reactive,
+    /// Added by `reactive` when drawing.
+    #[serde(skip)]
+    pub offset: CameraOffset,
 }
 
 impl Camera {
     pub fn new(
         camera_config: &CameraConfig,
         size: (u32, u32),
-        _config: &Config,
+        config: &Config,
         index: usize,
     ) -> Self {
         Self {
             position: camera_config.position.into(),
             yaw: cgmath::Deg(camera_config.yaw).into(),
             pitch: cgmath::Deg(camera_config.pitch).into(),
-            projection: Projection::new(size.0, size.1, cgmath::Deg(70.0), 0.1, 30_000.0),
+            projection: Projection::from_config(size.0, size.1, &camera_config.projection),
             controller: CameraController::new(10.0, 1.0),
             index,
+            transition: None,
+            mode: camera_config.mode.clone(),
+            look_at: None,
+            audio: AudioFrame::default(),
+            reactive: Reactive::new(config.camera_reactivity, config.seed),
+            offset: CameraOffset::default(),
         }
     }
 
+    /// Animates to a preset, keeping the controller.
+    pub fn transition_to(&mut self, target: &CameraConfig, index: usize, config: TransitionConfig) {
+        self.index = index;
+        self.mode = target.mode.clone();
+        self.transition = Some(Transition::new(
+            self.current_state(),
+            target.clone(),
+            config,
+        ));
+    }
+
+    fn set_state(&mut self, state: &CameraConfig) {
+        self.position = state.position.into();
+        self.yaw = Deg(state.yaw).into();
+        self.pitch = Deg(state.pitch).into();
+        self.projection.set_config(&state.projection);
+    }
+
+    pub fn direction(&self) -> Vector3<f32> {
+        let (sin_pitch, cos_pitch) = self.pitch.0.sin_cos();
+        let (sin_yaw, cos_yaw) = self.yaw.0.sin_cos();
+        Vector3::new(cos_pitch * cos_yaw, sin_pitch, cos_pitch * sin_yaw).normalize()
+    }
+
+    fn face(&mut self, target: Point3<f32>) {
+        let (yaw, pitch) = look_at_angles(self.position, target);
+        self.yaw = Deg(yaw).into();
+        self.pitch = Deg(pitch).into();
+    }
+
     pub fn update(&mut self, dt: f32) {
+        // An orbit waits for a transition into it to finish, so it starts
+        // from where the transition ends.
+        if matches!(self.mode, CameraMode::Orbit { .. }) && self.transition.is_none() {
+            self.orbit(dt);
+        } else {
+            self.fly(dt);
+        }
+        if matches!(self.mode, CameraMode::LookAt { .. }) {
+            if let Some(target) = self.look_at {
+                self.face(target);
+            }
+        }
+
+        if let Some(mut transition) = self.transition.take() {
+            transition.advance(dt);
+            self.set_state(&transition.current());
+            if !transition.is_done() {
+                self.transition = Some(transition);
+            }
+        }
+
+        // Keep the camera's angle from going too high/low.
+        if self.pitch < -Rad(SAFE_FRAC_PI_2) {
+            self.pitch = -Rad(SAFE_FRAC_PI_2);
+        } else if self.pitch > Rad(SAFE_FRAC_PI_2) {
+            self.pitch = Rad(SAFE_FRAC_PI_2);
+        }
+
+        self.offset = self.reactive.update(self.audio, dt, self.direction());
+    }
+
+    /// Circles the target. Turning moves around it and moving forward and
+    /// backward changes the distance.
+    fn orbit(&mut self, dt: f32) {
+        let controller = &mut self.controller;
+        if let CameraMode::Orbit {
+            target,
+            distance,
+            azimuth,
+            elevation,
+            speed,
+        } = &mut self.mode
+        {
+            *azimuth += *speed * dt
+                + (controller.rotate_horizontal * controller.sensitivity * dt).to_degrees();
+            *elevation = (*elevation
+                + (controller.rotate_vertical * controller.sensitivity * dt).to_degrees())
+            .clamp(-89.0, 89.0);
+            *distance = (*distance
+                + (controller.amount_backward - controller.amount_forward) * controller.speed * dt
+                - controller.scroll * controller.speed * controller.sensitivity * dt)
+                .max(1.0);
+            controller.rotate_horizontal = 0.0;
+            controller.rotate_vertical = 0.0;
+            controller.scroll = 0.0;
+
+            let target = Point3::from(*target);
+            self.position = orbit_position(target, *distance, *azimuth, *elevation);
+            let (yaw, pitch) = look_at_angles(self.position, target);
+            self.yaw = Deg(yaw).into();
+            self.pitch = Deg(pitch).into();
+        }
+    }
+
+    fn fly(&mut self, dt: f32) {
         // Move forward/backward and left/right
         let (yaw_sin, yaw_cos) = self.yaw.0.sin_cos();
         let forward = Vector3::new(yaw_cos, 0.0, yaw_sin).normalize();
@@ -86,13 +213,6 @@ impl Camera {
         // when moving in a non cardinal direction.
         self.controller.rotate_horizontal = 0.0;
         self.controller.rotate_vertical = 0.0;
-
-        // Keep the camera's angle from going too high/low.
-        if self.pitch < -Rad(SAFE_FRAC_PI_2) {
-            self.pitch = -Rad(SAFE_FRAC_PI_2);
-        } else if self.pitch > Rad(SAFE_FRAC_PI_2) {
-            self.pitch = Rad(SAFE_FRAC_PI_2);
-        }
     }
 
     pub fn current_state(&self) -> CameraConfig {
@@ -102,19 +222,57 @@ impl Camera {
             position: self.position.into(),
             yaw: yaw.0,
             pitch: pitch.0,
+            mode: self.mode.clone(),
+            projection: self.projection.config(),
         }
     }
 
-    pub fn calc_matrix(&self) -> Matrix4<f32> {
-        let (sin_pitch, cos_pitch) = self.pitch.0.sin_cos();
-        let (sin_yaw, cos_yaw) = self.yaw.0.sin_cos();
+    /// The current state as a keyframe at `t` seconds.
+    pub fn keyframe(&self, t: f32) -> Keyframe {
+        let CameraConfig {
+            position,
+            yaw,
+            pitch,
+            ..
+        } = self.current_state();
+        Keyframe {
+            t,
+            position,
+            yaw,
+            pitch,
+            fov: Deg::from(self.projection.fovy()).0,
+        }
+    }
+
+    pub fn apply_keyframe(&mut self, keyframe: &Keyframe) {
+        self.position = keyframe.position.into();
+        self.yaw = Deg(keyframe.yaw).into();
+        self.pitch = Deg(keyframe.pitch).into();
+        self.projection.set_fovy(Deg(keyframe.fov));
+    }
+
+    /// Where the camera is drawn from, including the reactive offset.
+    pub fn view_position(&self) -> Point3<f32> {
+        self.position + self.offset.position
+    }
 
+    pub fn calc_matrix(&self) -> Matrix4<f32> {
+        let pitch = (self.pitch + self.offset.pitch)
+            .0
+            .clamp(-SAFE_FRAC_PI_2, SAFE_FRAC_PI_2);
+        let (sin_pitch, cos_pitch) = pitch.sin_cos();
+        let (sin_yaw, cos_yaw) = (self.yaw + self.offset.yaw).0.sin_cos();
         Matrix4::look_to_rh(
-            self.position,
+            self.view_position(),
             Vector3::new(cos_pitch * cos_yaw, sin_pitch, cos_pitch * sin_yaw).normalize(),
             Vector3::unit_y(),
         )
     }
+
+    pub fn view_proj(&self) -> Matrix4<f32> {
+        let projection = self.projection.offset_fovy(self.offset.fovy);
+        projection.calc_matrix() * self.calc_matrix()
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -123,6 +281,9 @@ pub struct Projection {
     fovy: Rad<f32>,
     znear: f32,
     zfar: f32,
+    orthographic: bool,
+    /// World units shown vertically by the orthographic projection.
+    height: f32,
 }
 
 impl Projection {
@@ -132,18 +293,88 @@ impl Projection {
             fovy: fovy.into(),
             znear,
             zfar,
+            orthographic: false,
+            height: ProjectionConfig::default().height,
         }
     }
 
+    pub fn from_config(width: u32, height: u32, config: &ProjectionConfig) -> Self {
+        let mut projection = Self::new(width, height, Deg(config.fov), config.znear, config.zfar);
+        projection.set_config(config);
+        projection
+    }
+
+    pub fn config(&self) -> ProjectionConfig {
+        ProjectionConfig {
+            fov: Deg::from(self.fovy).0,
+            znear: self.znear,
+            zfar: self.zfar,
+            orthographic: self.orthographic,
+            height: self.height,
+        }
+    }
+
+    /// Applies edited settings, keeping the planes in a usable order.
+    pub fn set_config(&mut self, config: &ProjectionConfig) {
+        self.fovy = Deg(config.fov.clamp(1.0, 179.0)).into();
+        self.znear = config.znear.max(0.001);
+        self.zfar = config.zfar.max(self.znear * 2.0);
+        self.orthographic = config.orthographic;
+        self.height = config.height.max(0.001);
+    }
+
     pub fn resize(&mut self, width: u32, height: u32) {
         self.aspect = width as f32 / height as f32;
     }
 
+    pub fn fovy(&self) -> Rad<f32> {
+        self.fovy
+    }
+
+    pub fn set_fovy<F: Into<Rad<f32>>>(&mut self, fovy: F) {
+        self.fovy = fovy.into();
+    }
+
+    /// The projection with its field of view moved by `offset`, e.g. by the
+    /// fov pulse, within 1..179 degrees. Orthographic projections have no
+    /// field of view, their height is scaled by as much as the perspective's
+    /// view would widen instead.
+    pub fn offset_fovy(&self, offset: Rad<f32>) -> Self {
+        let min: Rad<f32> = Deg(1.0).into();
+        let max: Rad<f32> = Deg(179.0).into();
+        let fovy = Rad((self.fovy + offset).0.clamp(min.0, max.0));
+        let scale = (fovy / 2.0).tan() / (self.fovy / 2.0).tan();
+        Self {
+            fovy,
+            height: self.height * scale,
+            ..self.clone()
+        }
+    }
+
     pub fn calc_matrix(&self) -> Matrix4<f32> {
-        OPENGL_TO_WGPU_MATRIX * perspective(self.fovy, self.aspect, self.znear, self.zfar)
+        if self.orthographic {
+            let top = self.height / 2.0;
+            let right = top * self.aspect;
+            OPENGL_TO_WGPU_MATRIX * ortho(-right, right, -top, top, self.znear, self.zfar)
+        } else {
+            OPENGL_TO_WGPU_MATRIX * perspective(self.fovy, self.aspect, self.znear, self.zfar)
+        }
     }
 }
 
+/// What the controller's keys do.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Movement {
+    Forward,
+    Backward,
+    Left,
+    Right,
+    Up,
+    Down,
+    TurnLeft,
+    TurnRight,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct CameraController {
     amount_left: f32,
@@ -176,50 +407,30 @@ impl CameraController {
         }
     }
 
-    pub fn process_keyboard(&mut self, key: VirtualKeyCode, state: ElementState) -> bool {
+    /// Starts or stops a movement, as bound to keys in `KeyBindings`.
+    pub fn process_movement(&mut self, movement: Movement, state: ElementState) {
         let amount = if state == ElementState::Pressed {
             1.0
         } else {
             0.0
         };
-        match key {
-            VirtualKeyCode::W | VirtualKeyCode::Up => {
-                self.amount_forward = amount;
-                true
-            }
-            VirtualKeyCode::S | VirtualKeyCode::Down => {
-                self.amount_backward = amount;
-                true
-            }
-            VirtualKeyCode::A | VirtualKeyCode::Left => {
-                self.amount_left = amount;
-                true
-            }
-            VirtualKeyCode::D | VirtualKeyCode::Right => {
-                self.amount_right = amount;
-                true
-            }
-            VirtualKeyCode::K => {
-                self.amount_up = amount;
-                true
-            }
-            VirtualKeyCode::J => {
-                self.amount_down = amount;
-                true
-            }
-            VirtualKeyCode::H => {
-                self.rotate_horizontal = -amount * 2.0;
-                true
-            }
-            VirtualKeyCode::L => {
-                self.rotate_horizontal = amount * 2.0;
-                true
-            }
-
-            _ => false,
+        match movement {
+            Movement::Forward => self.amount_forward = amount,
+            Movement::Backward => self.amount_backward = amount,
+            Movement::Left => self.amount_left = amount,
+            Movement::Right => self.amount_right = amount,
+            Movement::Up => self.amount_up = amount,
+            Movement::Down => self.amount_down = amount,
+            Movement::TurnLeft => self.rotate_horizontal = -amount * 2.0,
+            Movement::TurnRight => self.rotate_horizontal = amount * 2.0,
         }
     }
 
+    /// Multiplies the movement speed, e.g. by 1.5 to move faster.
+    pub fn scale_speed(&mut self, factor: f32) {
+        self.speed = (self.speed * factor).clamp(0.1, 10_000.0);
+    }
+
     pub fn process_mouse(&mut self, mouse_dx: f64, mouse_dy: f64) {
         self.rotate_horizontal = mouse_dx as f32;
         self.rotate_vertical = mouse_dy as f32;
@@ -233,3 +444,23 @@ impl CameraController {
         };
     }
 }
+
+#[test]
+fn test_offset_fovy() {
+    let projection = Projection::new(16, 9, Deg(45.0), 0.1, 100.0);
+    let wide = projection.offset_fovy(Deg(500.0).into());
+    assert!((Deg::from(wide.fovy()).0 - 179.0).abs() < 1e-3);
+    let narrow = projection.offset_fovy(Deg(-500.0).into());
+    assert!((Deg::from(narrow.fovy()).0 - 1.0).abs() < 1e-3);
+
+    // Orthographic projections zoom by scaling their height.
+    let mut config = projection.config();
+    config.orthographic = true;
+    let orthographic = Projection::from_config(16, 9, &config);
+    let pulsed = orthographic.offset_fovy(Deg(10.0).into());
+    assert!(pulsed.height > orthographic.height);
+    assert_eq!(
+        orthographic.offset_fovy(Rad(0.0)).height,
+        orthographic.height
+    );
+}
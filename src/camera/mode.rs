@@ -0,0 +1,99 @@
+use cgmath::{InnerSpace, Point3, Vector3};
+use serde::{Deserialize, Serialize};
+
+/// How the camera is positioned and aimed each frame. Angles are in degrees
+/// and speeds in degrees per second.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum CameraMode {
+    /// Moved and turned by the `CameraController`.
+    Fly,
+    /// Circles a target point. The controller turns and zooms the orbit.
+    Orbit {
+        target: (f32, f32, f32),
+        distance: f32,
+        azimuth: f32,
+        elevation: f32,
+        speed: f32,
+    },
+    /// Flies like `Fly` but always faces the centroid of the live instances
+    /// of the named stream.
+    LookAt { stream: String },
+}
+
+impl Default for CameraMode {
+    fn default() -> Self {
+        CameraMode::Fly
+    }
+}
+
+impl CameraMode {
+    /// An orbit around the point `distance` in front of the camera, so that
+    /// switching to it doesn't move the camera.
+    pub fn orbit_from(
+        position: Point3<f32>,
+        direction: Vector3<f32>,
+        distance: f32,
+        speed: f32,
+    ) -> Self {
+        let target = position + direction.normalize() * distance;
+        let (azimuth, elevation) = orbit_angles(target, position);
+        CameraMode::Orbit {
+            target: target.into(),
+            distance,
+            azimuth,
+            elevation,
+            speed,
+        }
+    }
+}
+
+/// The camera position of an orbit.
+pub fn orbit_position(
+    target: Point3<f32>,
+    distance: f32,
+    azimuth: f32,
+    elevation: f32,
+) -> Point3<f32> {
+    let (az_sin, az_cos) = azimuth.to_radians().sin_cos();
+    let (el_sin, el_cos) = elevation.to_radians().sin_cos();
+    target + Vector3::new(el_cos * az_cos, el_sin, el_cos * az_sin) * distance
+}
+
+/// The azimuth and elevation of `position` seen from `target`.
+pub fn orbit_angles(target: Point3<f32>, position: Point3<f32>) -> (f32, f32) {
+    let offset = position - target;
+    let distance = offset.magnitude().max(f32::EPSILON);
+    (
+        offset.z.atan2(offset.x).to_degrees(),
+        (offset.y / distance).clamp(-1.0, 1.0).asin().to_degrees(),
+    )
+}
+
+/// The yaw and pitch, in degrees, that face `target` from `position`.
+pub fn look_at_angles(position: Point3<f32>, target: Point3<f32>) -> (f32, f32) {
+    let direction = target - position;
+    let distance = direction.magnitude().max(f32::EPSILON);
+    (
+        direction.z.atan2(direction.x).to_degrees(),
+        (direction.y / distance)
+            .clamp(-1.0, 1.0)
+            .asin()
+            .to_degrees(),
+    )
+}
+
+#[test]
+fn test_orbit_faces_target() {
+    let target = Point3::new(10.0, 0.0, 0.0);
+    let position = orbit_position(target, 100.0, 90.0, 30.0);
+    assert!(((position - target).magnitude() - 100.0).abs() < 1e-3);
+
+    let (azimuth, elevation) = orbit_angles(target, position);
+    assert!((azimuth - 90.0).abs() < 1e-3);
+    assert!((elevation - 30.0).abs() < 1e-3);
+
+    // Facing the target is facing away from the orbit's offset.
+    let (yaw, pitch) = look_at_angles(position, target);
+    assert!((yaw + 90.0).abs() < 1e-3);
+    assert!((pitch + 30.0).abs() < 1e-3);
+}
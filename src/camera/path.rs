@@ -0,0 +1,223 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use weresocool::error::Error;
+
+/// A camera state at a time in seconds. Angles and fov are in degrees.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Keyframe {
+    pub t: f32,
+    pub position: (f32, f32, f32),
+    pub yaw: f32,
+    pub pitch: f32,
+    pub fov: f32,
+}
+
+/// Timed keyframes the camera follows, saved per composition to
+/// `./save/paths/{composition}.json`. Positions are interpolated with
+/// Catmull-Rom splines, angles along the shortest arc.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CameraPath {
+    pub keyframes: Vec<Keyframe>,
+    /// Where edits are saved, `None` keeps them in memory.
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
+    /// The times of the keyframes inserted since loading, in order, for
+    /// `remove_last`.
+    #[serde(skip)]
+    pub recorded: Vec<f32>,
+}
+
+impl CameraPath {
+    pub fn path_for(composition: &str) -> PathBuf {
+        let stem = Path::new(composition)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_else(|| composition.to_string());
+        Path::new("./save/paths").join(format!("{}.json", stem))
+    }
+
+    /// Loads the path of a composition, or an empty one that is saved for it
+    /// once keyframes are recorded.
+    pub fn load_for(composition: &str) -> Result<Self, Error> {
+        let path = Self::path_for(composition);
+        let mut camera_path = if path.is_file() {
+            println!("Loading camera path from {}", path.display());
+            let data = std::fs::read_to_string(&path)?;
+            serde_json::from_str::<CameraPath>(&data).map_err(|e| {
+                Error::with_msg(format!("Unable to parse {}: {}", path.display(), e))
+            })?
+        } else {
+            CameraPath::default()
+        };
+        camera_path.sort();
+        camera_path.path = Some(path);
+        Ok(camera_path)
+    }
+
+    pub fn save(&self) -> Result<(), Error> {
+        if let Some(path) = &self.path {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let serialized = serde_json::to_string_pretty(self).map_err(|e| {
+                Error::with_msg(format!("Unable to serialize {}: {}", path.display(), e))
+            })?;
+            std::fs::write(path, serialized)?;
+        }
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keyframes.is_empty()
+    }
+
+    fn sort(&mut self) {
+        self.keyframes
+            .sort_by(|a, b| a.t.partial_cmp(&b.t).unwrap_or(std::cmp::Ordering::Equal));
+    }
+
+    /// Adds a keyframe, replacing one at the same time.
+    pub fn insert(&mut self, keyframe: Keyframe) {
+        self.keyframes
+            .retain(|existing| (existing.t - keyframe.t).abs() > 1e-3);
+        self.keyframes.push(keyframe);
+        self.sort();
+        self.recorded.push(keyframe.t);
+    }
+
+    /// Removes the keyframe inserted last, which isn't necessarily the latest
+    /// in time. Keyframes loaded from disk aren't removed.
+    pub fn remove_last(&mut self) {
+        while let Some(t) = self.recorded.pop() {
+            let found = self
+                .keyframes
+                .iter()
+                .position(|keyframe| (keyframe.t - t).abs() <= 1e-3);
+            if let Some(idx) = found {
+                self.keyframes.remove(idx);
+                return;
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.keyframes.clear();
+        self.recorded.clear();
+    }
+
+    /// The interpolated camera at `t`, holding the first and last keyframes
+    /// before and after the path.
+    pub fn sample(&self, t: f32) -> Option<Keyframe> {
+        let keyframes = &self.keyframes;
+        let first = keyframes.first()?;
+        let last = keyframes.last()?;
+        if t <= first.t {
+            return Some(Keyframe { t, ..*first });
+        }
+        if t >= last.t {
+            return Some(Keyframe { t, ..*last });
+        }
+
+        let idx = keyframes.iter().rposition(|k| k.t <= t)?;
+        let k1 = &keyframes[idx];
+        let k2 = &keyframes[idx + 1];
+        let k0 = &keyframes[idx.saturating_sub(1)];
+        let k3 = &keyframes[(idx + 2).min(keyframes.len() - 1)];
+        let u = if k2.t > k1.t {
+            (t - k1.t) / (k2.t - k1.t)
+        } else {
+            0.0
+        };
+
+        let spline = |f: fn(&Keyframe) -> f32| catmull_rom(f(k0), f(k1), f(k2), f(k3), u);
+        let angle = |f: fn(&Keyframe) -> f32| {
+            // Unwrap the neighbours around k1 so the spline takes the shortest arc.
+            let a1 = f(k1);
+            let a0 = a1 + shortest_arc(a1, f(k0));
+            let a2 = a1 + shortest_arc(a1, f(k2));
+            let a3 = a2 + shortest_arc(f(k2), f(k3));
+            catmull_rom(a0, a1, a2, a3, u)
+        };
+
+        Some(Keyframe {
+            t,
+            position: (
+                spline(|k| k.position.0),
+                spline(|k| k.position.1),
+                spline(|k| k.position.2),
+            ),
+            yaw: angle(|k| k.yaw),
+            pitch: angle(|k| k.pitch).clamp(-89.9, 89.9),
+            fov: spline(|k| k.fov),
+        })
+    }
+}
+
+/// The signed difference from `from` to `to` in degrees, in -180..180.
+pub fn shortest_arc(from: f32, to: f32) -> f32 {
+    (to - from + 180.0).rem_euclid(360.0) - 180.0
+}
+
+/// Uniform Catmull-Rom between p1 and p2.
+pub fn catmull_rom(p0: f32, p1: f32, p2: f32, p3: f32, u: f32) -> f32 {
+    let u2 = u * u;
+    let u3 = u2 * u;
+    0.5 * (2.0 * p1
+        + (p2 - p0) * u
+        + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * u2
+        + (3.0 * p1 - p0 - 3.0 * p2 + p3) * u3)
+}
+
+#[cfg(test)]
+fn keyframe(t: f32, x: f32, yaw: f32) -> Keyframe {
+    Keyframe {
+        t,
+        position: (x, 0.0, 0.0),
+        yaw,
+        pitch: 0.0,
+        fov: 70.0,
+    }
+}
+
+#[test]
+fn test_sample_passes_through_keyframes() {
+    let mut path = CameraPath::default();
+    path.insert(keyframe(2.0, 10.0, 0.0));
+    path.insert(keyframe(0.0, 0.0, 0.0));
+    path.insert(keyframe(4.0, 0.0, 0.0));
+
+    assert_eq!(path.sample(-1.0).unwrap().position.0, 0.0);
+    assert_eq!(path.sample(2.0).unwrap().position.0, 10.0);
+    assert_eq!(path.sample(10.0).unwrap().position.0, 0.0);
+    let x = path.sample(1.0).unwrap().position.0;
+    assert!(x > 0.0 && x < 10.0);
+
+    path.insert(keyframe(2.0, 5.0, 0.0));
+    assert_eq!(path.keyframes.len(), 3);
+    assert_eq!(path.sample(2.0).unwrap().position.0, 5.0);
+}
+
+#[test]
+fn test_angles_take_the_shortest_arc() {
+    assert_eq!(shortest_arc(170.0, -170.0), 20.0);
+    assert_eq!(shortest_arc(-170.0, 170.0), -20.0);
+
+    let mut path = CameraPath::default();
+    path.insert(keyframe(0.0, 0.0, 170.0));
+    path.insert(keyframe(1.0, 0.0, -170.0));
+    let yaw = path.sample(0.5).unwrap().yaw;
+    assert!((yaw - 180.0).abs() < 1e-3, "{}", yaw);
+}
+
+#[test]
+fn test_remove_last_recorded() {
+    let mut path = CameraPath::default();
+    path.insert(keyframe(4.0, 0.0, 0.0));
+    path.insert(keyframe(2.0, 0.0, 0.0));
+    path.remove_last();
+    let times: Vec<f32> = path.keyframes.iter().map(|keyframe| keyframe.t).collect();
+    assert_eq!(times, vec![4.0]);
+    path.remove_last();
+    path.remove_last();
+    assert!(path.is_empty());
+}
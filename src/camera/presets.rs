@@ -0,0 +1,233 @@
+use kintaro_egui_lib::CameraAction;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use weresocool::error::Error;
+
+use crate::config::CameraConfig;
+use crate::save::ConfigState;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CameraPreset {
+    pub name: String,
+    pub camera: CameraConfig,
+}
+
+/// The named cameras of a composition, saved to
+/// `./save/cameras/{composition}.json`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CameraPresets {
+    pub presets: Vec<CameraPreset>,
+    /// Where edits are saved, `None` keeps them in memory.
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
+}
+
+impl CameraPresets {
+    /// Names the cameras A, B, C... like the buttons did before presets.
+    pub fn from_cameras(cameras: Vec<CameraConfig>) -> Self {
+        Self {
+            presets: cameras
+                .into_iter()
+                .enumerate()
+                .map(|(idx, camera)| CameraPreset {
+                    name: char::from((idx % 26) as u8 + b'A').to_string(),
+                    camera,
+                })
+                .collect(),
+            path: None,
+        }
+    }
+
+    pub fn path_for(composition: &str) -> PathBuf {
+        let stem = Path::new(composition)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_else(|| composition.to_string());
+        Path::new("./save/cameras").join(format!("{}.json", stem))
+    }
+
+    /// Loads the presets of a composition, keeping `self` when it has none
+    /// saved yet. Later edits are saved for the composition either way.
+    ///
+    /// A camera left in `./save/saved.json` by older versions becomes the
+    /// first preset of compositions without presets, and is saved with them.
+    pub fn load_for(self, composition: &str) -> Result<Self, Error> {
+        let path = Self::path_for(composition);
+        let mut migrated = false;
+        let mut presets = if path.is_file() {
+            println!("Loading camera presets from {}", path.display());
+            let data = std::fs::read_to_string(&path)?;
+            serde_json::from_str::<CameraPresets>(&data).map_err(|e| {
+                Error::with_msg(format!("Unable to parse {}: {}", path.display(), e))
+            })?
+        } else {
+            let mut presets = self;
+            if let Some(camera) = ConfigState::saved_camera() {
+                println!(
+                    "Moving the camera saved in {} to {}",
+                    ConfigState::PATH,
+                    path.display()
+                );
+                presets.presets.insert(
+                    0,
+                    CameraPreset {
+                        name: "saved".into(),
+                        camera,
+                    },
+                );
+                migrated = true;
+            }
+            presets
+        };
+        if presets.presets.is_empty() {
+            return Err(Error::with_msg(format!(
+                "No camera presets in {}",
+                path.display()
+            )));
+        }
+        presets.path = Some(path);
+        if migrated {
+            presets.save()?;
+        }
+        Ok(presets)
+    }
+
+    pub fn save(&self) -> Result<(), Error> {
+        if let Some(path) = &self.path {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let serialized = serde_json::to_string_pretty(self).map_err(|e| {
+                Error::with_msg(format!("Unable to serialize {}: {}", path.display(), e))
+            })?;
+            std::fs::write(path, serialized)?;
+        }
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.presets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.presets.is_empty()
+    }
+
+    pub fn camera(&self, idx: usize) -> &CameraConfig {
+        &self.presets[idx.min(self.presets.len() - 1)].camera
+    }
+
+    pub fn position(&self, name: &str) -> Option<usize> {
+        self.presets.iter().position(|preset| preset.name == name)
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.presets
+            .iter()
+            .map(|preset| preset.name.to_owned())
+            .collect()
+    }
+
+    /// Applies an edit from the GUI and returns the index of the preset that
+    /// should be selected afterwards.
+    pub fn apply(&mut self, action: CameraAction, selected: usize, current: CameraConfig) -> usize {
+        let n = self.presets.len();
+        match action {
+            CameraAction::Add(name) => {
+                self.presets.push(CameraPreset {
+                    name,
+                    camera: current,
+                });
+                self.presets.len() - 1
+            }
+            CameraAction::Update(idx) => {
+                if let Some(preset) = self.presets.get_mut(idx) {
+                    preset.camera = current;
+                }
+                selected
+            }
+            CameraAction::Rename(idx, name) => {
+                if let Some(preset) = self.presets.get_mut(idx) {
+                    preset.name = name;
+                }
+                selected
+            }
+            CameraAction::Delete(idx) => {
+                if n <= 1 || idx >= n {
+                    return selected;
+                }
+                self.presets.remove(idx);
+                if selected > idx || selected == n - 1 {
+                    selected - 1
+                } else {
+                    selected
+                }
+            }
+            CameraAction::Move(idx, offset) => {
+                let to = idx as isize + offset;
+                if idx >= n || to < 0 || to >= n as isize {
+                    return selected;
+                }
+                let to = to as usize;
+                let preset = self.presets.remove(idx);
+                self.presets.insert(to, preset);
+                if selected == idx {
+                    to
+                } else if selected == to {
+                    idx
+                } else {
+                    selected
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_apply_camera_actions() {
+    let camera = |yaw| CameraConfig {
+        position: (0.0, 0.0, 0.0),
+        yaw,
+        pitch: 0.0,
+        mode: Default::default(),
+        projection: Default::default(),
+    };
+    let mut presets = CameraPresets::from_cameras(vec![camera(1.0), camera(2.0), camera(3.0)]);
+    assert_eq!(presets.names(), vec!["A", "B", "C"]);
+
+    assert_eq!(presets.apply(CameraAction::Move(0, 1), 0, camera(0.0)), 1);
+    assert_eq!(presets.names(), vec!["B", "A", "C"]);
+    assert_eq!(presets.apply(CameraAction::Move(0, -1), 1, camera(0.0)), 1);
+
+    assert_eq!(
+        presets.apply(CameraAction::Add("D".into()), 1, camera(4.0)),
+        3
+    );
+    assert_eq!(presets.camera(3).yaw, 4.0);
+
+    presets.apply(CameraAction::Rename(0, "first".into()), 3, camera(0.0));
+    assert_eq!(presets.names(), vec!["first", "A", "C", "D"]);
+
+    assert_eq!(presets.apply(CameraAction::Delete(3), 3, camera(0.0)), 2);
+    assert_eq!(presets.apply(CameraAction::Delete(0), 2, camera(0.0)), 1);
+    assert_eq!(presets.names(), vec!["A", "C"]);
+
+    presets.apply(CameraAction::Update(1), 1, camera(9.0));
+    assert_eq!(presets.camera(1).yaw, 9.0);
+}
+
+#[test]
+fn test_presets_saved_before_projections_load() {
+    let json = r#"{"presets": [{"name": "A", "camera": {"position": [0.0, 1.0, 2.0], "yaw": 3.0, "pitch": 4.0}}]}"#;
+    let presets: CameraPresets = serde_json::from_str(json).unwrap();
+    assert_eq!(
+        presets.camera(0).projection,
+        kintaro_egui_lib::ProjectionConfig::default()
+    );
+
+    let mut camera = presets.camera(0).clone();
+    camera.projection.orthographic = true;
+    let saved = serde_json::to_string(&camera).unwrap();
+    let loaded: CameraConfig = serde_json::from_str(&saved).unwrap();
+    assert!(loaded.projection.orthographic);
+}
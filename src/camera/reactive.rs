@@ -0,0 +1,152 @@
+use cgmath::{Deg, Rad, Vector3, Zero};
+use kintaro_egui_lib::CameraReactivity;
+use rand::Rng;
+
+use crate::audio::analysis::AudioFrame;
+use crate::rng::{stream_rng, GenRng};
+
+/// World units the camera shakes at full intensity.
+const SHAKE_DISTANCE: f32 = 4.0;
+const SHAKE_ANGLE: f32 = 1.5;
+const SHAKE_HZ: f32 = 9.0;
+/// Degrees the field of view narrows at full intensity.
+const FOV_PULSE: f32 = 8.0;
+/// World units the camera moves forward at full intensity and energy.
+const DOLLY_DISTANCE: f32 = 60.0;
+/// Seconds for a pulse to decay to a third.
+const PULSE_DECAY: f32 = 0.15;
+/// Seconds for the sustained energy to follow the music.
+const SUSTAIN: f32 = 4.0;
+
+/// Applied on top of the camera when drawing, so the controller, presets and
+/// paths never see it.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct CameraOffset {
+    pub position: Vector3<f32>,
+    pub yaw: Rad<f32>,
+    pub pitch: Rad<f32>,
+    pub fovy: Rad<f32>,
+}
+
+impl Default for CameraOffset {
+    fn default() -> Self {
+        Self {
+            position: Vector3::zero(),
+            yaw: Rad(0.0),
+            pitch: Rad(0.0),
+            fovy: Rad(0.0),
+        }
+    }
+}
+
+/// Shakes, zooms and dollies the camera with the music. It only depends on
+/// the audio frames and frame times it is given, so print renders the same
+/// motion for the same seed.
+#[derive(Clone, Debug)]
+pub struct Reactive {
+    pub reactivity: CameraReactivity,
+    /// Jumps up on onsets and decays quickly.
+    pulse: f32,
+    /// Slowly follows the energy.
+    sustained: f32,
+    elapsed: f32,
+    /// Phases of the shake's sines, one per axis and angle.
+    phases: [f32; 5],
+}
+
+impl Default for Reactive {
+    fn default() -> Self {
+        Self::new(CameraReactivity::default(), 0)
+    }
+}
+
+impl Reactive {
+    pub fn new(reactivity: CameraReactivity, seed: u64) -> Self {
+        let mut rng: GenRng = stream_rng(seed, "camera shake");
+        let mut phases = [0.0; 5];
+        phases
+            .iter_mut()
+            .for_each(|phase| *phase = rng.gen::<f32>() * std::f32::consts::TAU);
+        Self {
+            reactivity,
+            pulse: 0.0,
+            sustained: 0.0,
+            elapsed: 0.0,
+            phases,
+        }
+    }
+
+    /// Advances by `dt` seconds and returns the offset for the frame, facing
+    /// `direction`.
+    pub fn update(&mut self, frame: AudioFrame, dt: f32, direction: Vector3<f32>) -> CameraOffset {
+        self.elapsed += dt;
+        self.pulse = (self.pulse * (-dt / PULSE_DECAY).exp()).max(frame.onset);
+        self.sustained += (frame.energy - self.sustained) * (1.0 - (-dt / SUSTAIN).exp());
+
+        let CameraReactivity {
+            shake,
+            fov_pulse,
+            dolly,
+        } = self.reactivity;
+        let shake = shake * self.pulse;
+        let noise = |idx: usize, hz: f32| {
+            (self.elapsed * std::f32::consts::TAU * hz + self.phases[idx]).sin()
+        };
+
+        CameraOffset {
+            position: Vector3::new(
+                noise(0, SHAKE_HZ),
+                noise(1, SHAKE_HZ * 1.31),
+                noise(2, SHAKE_HZ * 0.87),
+            ) * shake
+                * SHAKE_DISTANCE
+                + direction * dolly * self.sustained * DOLLY_DISTANCE,
+            yaw: Deg(noise(3, SHAKE_HZ * 0.73) * shake * SHAKE_ANGLE).into(),
+            pitch: Deg(noise(4, SHAKE_HZ * 1.17) * shake * SHAKE_ANGLE).into(),
+            fovy: Deg(-fov_pulse * self.pulse * FOV_PULSE).into(),
+        }
+    }
+}
+
+#[test]
+fn test_reactive_is_deterministic_and_quiet_without_audio() {
+    let reactivity = CameraReactivity {
+        shake: 1.0,
+        fov_pulse: 1.0,
+        dolly: 1.0,
+    };
+    let direction = Vector3::unit_x();
+    let beat = AudioFrame {
+        energy: 1.0,
+        onset: 1.0,
+    };
+
+    let mut silent = Reactive::new(reactivity, 1);
+    assert_eq!(
+        silent.update(AudioFrame::default(), 0.025, direction),
+        CameraOffset::default()
+    );
+
+    let run = |seed| {
+        let mut reactive = Reactive::new(reactivity, seed);
+        (0..40)
+            .map(|idx| {
+                reactive.update(
+                    if idx % 20 == 0 {
+                        beat
+                    } else {
+                        AudioFrame::default()
+                    },
+                    0.025,
+                    direction,
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+    let offsets = run(1);
+    assert_eq!(offsets, run(1));
+    assert_ne!(offsets, run(2));
+    // A beat narrows the field of view, which then recovers.
+    assert!(offsets[0].fovy.0 < offsets[10].fovy.0);
+    assert!(offsets[20].fovy.0 < offsets[19].fovy.0);
+}
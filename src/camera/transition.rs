@@ -0,0 +1,112 @@
+use kintaro_egui_lib::ProjectionConfig;
+use serde::{Deserialize, Serialize};
+
+use super::path::shortest_arc;
+use crate::config::CameraConfig;
+use crate::easing::Easing;
+
+/// How the camera moves to a newly selected preset. A duration of zero snaps.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+pub struct TransitionConfig {
+    pub duration: f32,
+    pub easing: Easing,
+}
+
+impl Default for TransitionConfig {
+    fn default() -> Self {
+        Self {
+            duration: 2.0,
+            easing: Easing::InOutCubic,
+        }
+    }
+}
+
+/// Moves to the named preset `t` seconds into the composition.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CameraCue {
+    pub t: f32,
+    pub camera: String,
+}
+
+/// An animation from one camera state to another, advanced by
+/// `Camera::update`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Transition {
+    pub from: CameraConfig,
+    pub to: CameraConfig,
+    pub elapsed: f32,
+    pub config: TransitionConfig,
+}
+
+impl Transition {
+    pub fn new(from: CameraConfig, to: CameraConfig, config: TransitionConfig) -> Self {
+        Self {
+            from,
+            to,
+            elapsed: 0.0,
+            config,
+        }
+    }
+
+    pub fn advance(&mut self, dt: f32) {
+        self.elapsed += dt;
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.elapsed >= self.config.duration
+    }
+
+    pub fn current(&self) -> CameraConfig {
+        let u = if self.config.duration > 0.0 {
+            self.config.easing.ease(self.elapsed / self.config.duration)
+        } else {
+            1.0
+        };
+        let lerp = |a: f32, b: f32| a + (b - a) * u;
+        let (from, to) = (&self.from, &self.to);
+
+        CameraConfig {
+            position: (
+                lerp(from.position.0, to.position.0),
+                lerp(from.position.1, to.position.1),
+                lerp(from.position.2, to.position.2),
+            ),
+            yaw: from.yaw + shortest_arc(from.yaw, to.yaw) * u,
+            pitch: lerp(from.pitch, to.pitch),
+            mode: to.mode.clone(),
+            projection: ProjectionConfig {
+                fov: lerp(from.projection.fov, to.projection.fov),
+                height: lerp(from.projection.height, to.projection.height),
+                ..to.projection
+            },
+        }
+    }
+}
+
+#[test]
+fn test_transition_eases_to_target() {
+    let camera = |x, yaw| CameraConfig {
+        position: (x, 0.0, 0.0),
+        yaw,
+        pitch: 0.0,
+        mode: Default::default(),
+        projection: Default::default(),
+    };
+    let mut transition = Transition::new(
+        camera(0.0, 170.0),
+        camera(10.0, -170.0),
+        TransitionConfig {
+            duration: 2.0,
+            easing: Easing::Linear,
+        },
+    );
+    transition.advance(1.0);
+    let halfway = transition.current();
+    assert_eq!(halfway.position.0, 5.0);
+    assert_eq!(halfway.yaw, 180.0);
+    assert!(!transition.is_done());
+
+    transition.advance(1.0);
+    assert!(transition.is_done());
+    assert_eq!(transition.current().position.0, 10.0);
+}
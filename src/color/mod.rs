@@ -7,6 +7,7 @@ use rand::seq::SliceRandom;
 
 use crate::gen::GenColor;
 use crate::op_stream::OpStream;
+use crate::rng::GenRng;
 use crate::{colorset_from_hex_strings, colorsets_from_vec_hex_strings, vec_hex_to_vec_color};
 
 pub type NamedValue<'a, T> = (&'a str, T);
@@ -20,8 +21,7 @@ pub struct RandColorSet {
 }
 
 impl RandColorSet {
-    pub fn init(n: usize) -> Self {
-        let mut rng = rand::thread_rng();
+    pub fn init(n: usize, rng: &mut GenRng) -> Self {
         let mut r = || rng.gen::<f32>() * 2.0 - 1.0;
 
         RandColorSet {
@@ -126,13 +126,13 @@ pub struct ColorMap {
 }
 
 impl GenColor for ColorMap {
-    fn gen(&self, op_stream: &OpStream) -> Color {
+    fn gen(&self, op_stream: &OpStream, rng: &mut GenRng) -> Color {
         for (name, color) in self.colors.iter() {
             if op_stream.names.contains(name) {
-                return color.gen(op_stream);
+                return color.gen(op_stream, rng);
             }
         }
-        self.default.gen(op_stream)
+        self.default.gen(op_stream, rng)
     }
     fn update(&mut self) {}
 }
@@ -146,8 +146,8 @@ pub struct Color {
 }
 
 impl GenColor for ColorSets {
-    fn gen(&self, op_stream: &OpStream) -> Color {
-        self.colorsets[self.n].gen(op_stream)
+    fn gen(&self, op_stream: &OpStream, rng: &mut GenRng) -> Color {
+        self.colorsets[self.n].gen(op_stream, rng)
     }
     fn update(&mut self) {
         self.n = (self.n + 1) % self.colorsets.len();
@@ -155,9 +155,9 @@ impl GenColor for ColorSets {
 }
 
 impl GenColor for ColorSet {
-    fn gen(&self, _op_stream: &OpStream) -> Color {
+    fn gen(&self, _op_stream: &OpStream, rng: &mut GenRng) -> Color {
         self.colors
-            .choose(&mut rand::thread_rng())
+            .choose(rng)
             .expect("color choice failed")
             .to_owned()
     }
@@ -165,8 +165,7 @@ impl GenColor for ColorSet {
 }
 
 impl GenColor for RandColor {
-    fn gen(&self, _op_stream: &OpStream) -> Color {
-        let mut rng = rand::thread_rng();
+    fn gen(&self, _op_stream: &OpStream, rng: &mut GenRng) -> Color {
         let mut r = || rng.gen::<f32>() * 2.0 - 1.0;
 
         Color {
@@ -180,9 +179,9 @@ impl GenColor for RandColor {
 }
 
 impl GenColor for RandColorSet {
-    fn gen(&self, _op_stream: &OpStream) -> Color {
+    fn gen(&self, _op_stream: &OpStream, rng: &mut GenRng) -> Color {
         self.colors
-            .choose(&mut rand::thread_rng())
+            .choose(rng)
             .expect("color choice failed")
             .to_owned()
     }
@@ -1,7 +1,7 @@
 pub mod render;
 use crate::{
-    camera::Camera, canvas::Canvas, config::Config, image_renderer::ImageRenderer,
-    shared::RenderPassInput, toy::Toy,
+    audio::analysis::AudioFeatures, camera::Camera, canvas::Canvas, config::Config,
+    image_renderer::ImageRenderer, osc::OscOutput, shared::RenderPassInput, toy::Toy,
 };
 
 pub struct Composition {
@@ -10,6 +10,12 @@ pub struct Composition {
     pub camera: Camera,
     pub toy: Option<Toy>,
     pub image_renderer: Option<ImageRenderer>,
+    /// Drive the camera from `config.camera_path` instead of the controller.
+    pub follow_path: bool,
+    /// What the camera reacts to, sampled at the clock's time.
+    pub features: AudioFeatures,
+    /// Receives the playhead and each stream's ops as they are played.
+    pub osc: Option<OscOutput>,
     pub config: Config,
 }
 
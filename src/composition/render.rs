@@ -1,10 +1,14 @@
+use crate::camera::mode::CameraMode;
 use crate::clock::ClockResult;
 use crate::composition::Canvas;
 use crate::instance::instancer::{op4d_to_instance, prepare_op4d_to_instancer_input, Instancer};
-use crate::instance::{make_instance_buffer, Instance};
+use crate::instance::{centroid, make_instance_buffer, Instance};
+use crate::op_stream::{EventType, Op4D};
+use crate::osc::OscOutput;
 use crate::shared::RenderPassInput;
 use crate::toy::toy_renderpass;
 use crate::vertex::make_vertex_buffer;
+use cgmath::Point3;
 use kintaro_egui_lib::InstanceMul;
 use wgpu::TextureView;
 
@@ -22,25 +26,52 @@ impl Composition {
         clock: &impl Clock,
         instance_mul: InstanceMul,
         view: &TextureView,
+    ) {
+        self.advance(device, size, clock, instance_mul);
+        self.draw(device, queue, encoder, view);
+    }
+
+    /// Moves the camera and instances to the clock's time.
+    pub fn advance(
+        &mut self,
+        device: &wgpu::Device,
+        size: (u32, u32),
+        clock: &impl Clock,
+        instance_mul: InstanceMul,
     ) {
         let time = clock.current();
+        if clock.is_playing() {
+            self.cue_camera(time);
+        }
+        if let (Some(osc), true) = (&self.osc, clock.is_playing()) {
+            osc.playhead(time.total_elapsed);
+        }
+        self.camera.look_at = self.look_at_target();
+        self.camera.audio = self.features.at(time.total_elapsed);
         self.camera.update(time.last_period);
-
-        let view_position: [f32; 4] = self.camera.position.to_homogeneous().into();
-        let view_proj: [[f32; 4]; 4] =
-            (self.camera.projection.calc_matrix() * self.camera.calc_matrix()).into();
+        if self.follow_path {
+            if let Some(keyframe) = self.config.camera_path.sample(time.total_elapsed) {
+                self.camera.apply_keyframe(&keyframe);
+            }
+        }
 
         for idx in 0..self.renderpasses.len() {
-            self.update(
-                clock.is_playing(),
-                time,
-                idx,
-                device,
-                queue,
-                size,
-                instance_mul,
-            );
+            self.update(clock.is_playing(), time, idx, device, size, instance_mul);
         }
+    }
+
+    /// Draws the current state into `view` without advancing it, so a frame
+    /// can be drawn again, e.g. offscreen for a screenshot. The view's size
+    /// is taken from the camera's projection.
+    pub fn draw(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &TextureView,
+    ) {
+        let view_position: [f32; 4] = self.camera.view_position().to_homogeneous().into();
+        let view_proj: [[f32; 4]; 4] = self.camera.view_proj().into();
 
         if let Some(image_renderer) = &mut self.image_renderer {
             image_renderer
@@ -53,23 +84,69 @@ impl Composition {
         // .expect("toy error");
         // }
 
-        for (n, renderpass) in self.renderpasses.iter_mut().enumerate() {
+        for (n, renderpass) in self
+            .renderpasses
+            .iter_mut()
+            .filter(|renderpass| renderpass.visible)
+            .enumerate()
+        {
             renderpass
                 .uniforms
                 .update_view_proj(view_position, view_proj);
+            queue.write_buffer(
+                &renderpass.uniform_buffer,
+                0,
+                bytemuck::cast_slice(&[renderpass.uniforms]),
+            );
 
             let accumulation = n > 0 || self.toy.is_some();
             renderpass.render(encoder, &view, &self.config, accumulation);
         }
     }
 
+    /// The centroid of the stream a `CameraMode::LookAt` camera follows,
+    /// matched by its full name or one of its names.
+    fn look_at_target(&self) -> Option<Point3<f32>> {
+        let stream = match &self.camera.mode {
+            CameraMode::LookAt { stream } => stream,
+            _ => return None,
+        };
+        self.renderpasses
+            .iter()
+            .find(|renderpass| {
+                renderpass.op_stream.name() == *stream
+                    || renderpass.op_stream.names.iter().any(|name| name == stream)
+            })
+            .and_then(|renderpass| centroid(&renderpass.instances))
+    }
+
+    /// Starts the transition of the latest cue within the last frame.
+    fn cue_camera(&mut self, time: ClockResult) {
+        let start = time.total_elapsed - time.last_period;
+        let cue = self
+            .config
+            .camera_cues
+            .iter()
+            .filter(|cue| cue.t > start && cue.t <= time.total_elapsed)
+            .last();
+        if let Some(cue) = cue {
+            match self.config.cameras.position(&cue.camera) {
+                Some(index) => self.camera.transition_to(
+                    self.config.cameras.camera(index),
+                    index,
+                    self.config.camera_transition,
+                ),
+                None => println!("no camera preset named {}", cue.camera),
+            }
+        }
+    }
+
     pub fn update(
         &mut self,
         is_playing: bool,
         time: ClockResult,
         idx: usize,
         device: &wgpu::Device,
-        queue: &wgpu::Queue,
         size: (u32, u32),
         instance_mul: InstanceMul,
     ) {
@@ -80,53 +157,77 @@ impl Composition {
         self.renderpasses[idx].vertex_buffer =
             make_vertex_buffer(device, self.renderpasses[idx].vertices.as_slice());
 
+        let instance_mul = self.renderpasses[idx].instance_mul.unwrap_or(instance_mul);
         if is_playing {
             update_instances(
                 &time,
+                idx,
                 &mut self.renderpasses[idx],
                 &self.canvas,
                 device,
-                &*self.config.instancer,
                 size,
                 instance_mul,
+                self.osc.as_ref(),
             );
         }
         // renderpass.vertices.iter_mut().for_each(|v| v.update());
-        queue.write_buffer(
-            &self.renderpasses[idx].uniform_buffer,
-            0,
-            bytemuck::cast_slice(&[self.renderpasses[idx].uniforms]),
-        );
     }
 }
 
 fn update_instances(
     time: &ClockResult,
+    idx: usize,
     renderpass: &mut RenderPassInput,
     canvas: &Canvas,
     device: &wgpu::Device,
-    instancer: &(impl Instancer + ?Sized),
     size: (u32, u32),
     mul: InstanceMul,
+    osc: Option<&OscOutput>,
 ) {
-    let mut new_instances: Vec<Instance> = renderpass
+    let instancer = &renderpass.instancer;
+    let envelope = renderpass.envelope;
+    let rng = &mut renderpass.rng;
+    let (offs, ons): (Vec<Op4D>, Vec<Op4D>) = renderpass
         .op_stream
         .get_batch(time.total_elapsed)
+        .into_iter()
+        .partition(|op| matches!(op.event_type, EventType::Off));
+    if let Some(osc) = osc {
+        osc.stream_events(idx, &renderpass.op_stream.name(), &ons, &offs);
+    }
+
+    let mut new_instances: Vec<Instance> = ons
         .into_iter()
         .map(|op| {
             let input = prepare_op4d_to_instancer_input(&mul, &op);
-            let transformation = instancer.op4d_to_instance_transformation(input);
-            op4d_to_instance(transformation, op, canvas)
+            let transformation = instancer.op4d_to_instance_transformation(input, rng);
+            let mut instance = op4d_to_instance(transformation, op, canvas);
+            instance.envelope = envelope;
+            instance
         })
         .collect();
 
     renderpass.instances.append(&mut new_instances);
-    renderpass
-        .instances
-        .iter_mut()
-        .for_each(|i| instancer.update_instance(i, time.last_period));
+    // After appending, so notes that start and end in one batch are released.
+    offs.iter().for_each(|op| {
+        renderpass
+            .instances
+            .iter_mut()
+            .filter(|instance| instance.held && instance.is_op(op))
+            .for_each(|instance| instance.release());
+    });
+
+    // Holding a note only sustains its envelope, instances move and age
+    // either way.
+    renderpass.instances.iter_mut().for_each(|i| {
+        i.age += time.last_period;
+        if i.held && i.age >= i.hold {
+            i.release();
+        }
+        instancer.update_instance(i, time.last_period)
+    });
 
-    renderpass.instances.retain(|i| i.life > 0.0);
+    renderpass.instances.retain(Instance::is_alive);
     renderpass.instance_buffer =
         make_instance_buffer(&renderpass.instances, (size.0, size.1), &device);
 }
@@ -1,14 +1,44 @@
-use kintaro_egui_lib::InstanceMul;
+use kintaro_egui_lib::{CameraReactivity, InstanceMul, ProjectionConfig};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 use crate::camera::default::default_cameras;
+use crate::camera::mode::CameraMode;
+use crate::camera::path::CameraPath;
+use crate::camera::presets::CameraPresets;
+use crate::camera::transition::{CameraCue, TransitionConfig};
+use crate::data::DataConfig;
+use crate::instance::envelope::Envelope;
 use crate::instance::instancer::{Instancer, SimpleInstancer};
+use crate::midi::MidiMapping;
+use crate::op_stream::transform::OpTransform;
+use crate::osc::OscConfig;
+use crate::realtime::bindings::KeyBindings;
+use crate::rng::random_seed;
 use crate::save::ConfigState;
+use crate::shared::DevicePolicy;
+use crate::stream_map::StreamMap;
 use crate::vertex::shape::{RandIndex, RandPosition, Shape};
 use crate::ColorSets;
 
 impl Default for Config {
+    /// `Config::unsaved` with the state saved in `./save/saved.json`. Cameras
+    /// are saved per composition instead, see `CameraPresets::load_for`.
     fn default() -> Self {
+        let config = Config::unsaved();
+        let (instance_mul, stream_instance_muls, seed) = Config::handle_save(config.instance_mul);
+        Config {
+            instance_mul,
+            stream_instance_muls,
+            seed: seed.unwrap_or(config.seed),
+            ..config
+        }
+    }
+}
+
+impl Config {
+    /// The defaults without reading any saved state, e.g. for tests.
+    pub fn unsaved() -> Self {
         let instance_mul = InstanceMul {
             x: 9.0,
             y: 19.0,
@@ -17,74 +47,135 @@ impl Default for Config {
             size: 23.0,
             length: 1.0,
         };
-        let (cameras, instance_mul) = Config::handle_save(instance_mul);
         Config {
             instance_shader: "./src/shader.wgsl".into(),
             toy_shader: "./src/toy.wgsl".into(),
-            instancer: Box::new(SimpleInstancer {}),
+            transforms: StreamMap::init(vec![]),
+            instancers: StreamMap::init(Box::new(SimpleInstancer {}) as Box<dyn Instancer>),
+            envelopes: StreamMap::init(Envelope::default()),
             instance_mul,
+            stream_instance_muls: BTreeMap::new(),
+            seed: random_seed(),
             accumulation: false,
+            device: DevicePolicy::default(),
             filename: "kintaro".into(),
             volume: 0.20,
+            stems: false,
+            source: Source::Socool,
+            render_cache: Some("./cache".into()),
+            live_reload: true,
             window_size: (2560, 1440),
-            cameras,
-            shape: Shape {
+            screenshot_size: None,
+            cameras: CameraPresets::from_cameras(default_cameras(vec![], Some((0.0, 20.0, 0.0)))),
+            camera_path: CameraPath::default(),
+            camera_transition: TransitionConfig::default(),
+            camera_cues: vec![],
+            camera_reactivity: CameraReactivity::default(),
+            key_bindings: KeyBindings::default(),
+            midi: MidiMapping::default(),
+            osc: OscConfig::default(),
+            remote: None,
+            shapes: StreamMap::init(Shape {
                 n_vertices: 70,
                 n_indices: 70,
                 position: Box::new(RandPosition),
                 color: Box::new(ColorSets::default()),
                 indices: Box::new(RandIndex),
-            },
+            }),
         }
     }
-}
 
-impl Config {
-    pub fn handle_save(instance_mul: InstanceMul) -> (Vec<CameraConfig>, InstanceMul) {
+    pub fn handle_save(
+        instance_mul: InstanceMul,
+    ) -> (InstanceMul, BTreeMap<String, InstanceMul>, Option<u64>) {
         let saved = ConfigState::load_saved();
-        let cameras = default_cameras(
-            if let Ok(ref s) = saved {
-                if s.is_some() {
-                    vec![s.as_ref().unwrap().camera]
-                } else {
-                    vec![]
-                }
-            } else {
-                vec![]
-            },
-            Some((0.0, 20.0, 0.0)),
-        );
-
-        let instance_mul = if let Ok(s) = saved {
+        let (instance_mul, stream_instance_muls, seed) = if let Ok(s) = saved {
             if s.is_some() {
-                s.unwrap().instance_mul
+                let s = s.unwrap();
+                (s.instance_mul, s.stream_instance_muls, s.seed)
             } else {
-                instance_mul
+                (instance_mul, BTreeMap::new(), None)
             }
         } else {
-            instance_mul
+            (instance_mul, BTreeMap::new(), None)
         };
-        (cameras, instance_mul)
+        (instance_mul, stream_instance_muls, seed)
     }
 }
 
-#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CameraConfig {
     pub position: (f32, f32, f32),
     pub yaw: f32,
     pub pitch: f32,
+    #[serde(default)]
+    pub mode: CameraMode,
+    #[serde(default)]
+    pub projection: ProjectionConfig,
+}
+
+/// Where the op streams and audio of a piece come from.
+#[derive(Clone, Debug)]
+pub enum Source {
+    /// Render the `.socool` file passed to `application::run` with weresocool.
+    Socool,
+    /// Read pre-rendered op streams from `OpStream::json_path` of the
+    /// composition passed to `application::run`, e.g. `kintaro.socool.json`,
+    /// and play the optional wav file alongside them.
+    Json { audio: Option<String> },
+    /// Visualize csv data directly.
+    Data(DataConfig),
 }
 
 #[derive(Clone)]
 pub struct Config {
     pub filename: String,
     pub volume: f32,
+    /// Render each named voice as its own audio stem so that it can be muted,
+    /// soloed and mixed alongside its op stream.
+    pub stems: bool,
+    pub source: Source,
+    /// Directory for cached weresocool renders, `None` always renders.
+    pub render_cache: Option<String>,
+    /// Re-render the `.socool` file in realtime mode whenever it changes.
+    pub live_reload: bool,
     pub window_size: (u32, u32),
-    pub cameras: Vec<CameraConfig>,
+    /// Resolution of realtime screenshots, `None` uses the window's.
+    /// Overridden by `--screenshot-size WxH`.
+    pub screenshot_size: Option<(u32, u32)>,
+    /// Which adapter to render on, overridden by `KINTARO_*` environment
+    /// variables and `--software`.
+    pub device: DevicePolicy,
+    /// Replaced by the composition's saved presets in `application::run`.
+    pub cameras: CameraPresets,
+    /// Followed in print mode and, when enabled in the GUI, in realtime.
+    pub camera_path: CameraPath,
+    pub camera_transition: TransitionConfig,
+    /// Preset changes at times in the composition.
+    pub camera_cues: Vec<CameraCue>,
+    /// How much the camera shakes, zooms and dollies with the audio.
+    pub camera_reactivity: CameraReactivity,
+    /// Realtime keys, replaced by `./save/keys.json` when it exists.
+    pub key_bindings: KeyBindings,
+    /// Midi controls, edited with midi learn and saved to `./save/midi.json`.
+    pub midi: MidiMapping,
+    /// Realtime OSC control and event output, off by default.
+    pub osc: OscConfig,
+    /// Where the remote control panel is served, `None` disables it.
+    pub remote: Option<String>,
     pub accumulation: bool,
-    pub shape: Shape,
+    pub shapes: StreamMap<Shape>,
     pub instance_mul: InstanceMul,
-    pub instancer: Box<dyn Instancer>,
+    /// Per op stream overrides of `instance_mul`, keyed by `OpStream::name`.
+    pub stream_instance_muls: BTreeMap<String, InstanceMul>,
+    /// Seeds shape, color and instance generation. Saved with the config
+    /// state so a take can be reproduced.
+    pub seed: u64,
+    pub instancers: StreamMap<Box<dyn Instancer>>,
+    /// Size, alpha and color intensity envelopes of each stream's instances.
+    pub envelopes: StreamMap<Envelope>,
+    /// Applied to the ops of each stream before they become instances.
+    pub transforms: StreamMap<Vec<Box<dyn OpTransform>>>,
     pub instance_shader: String,
     pub toy_shader: String,
 }
@@ -0,0 +1,398 @@
+use std::cmp::Ordering;
+use std::path::Path;
+use weresocool::error::Error;
+
+use crate::op_stream::{EventType, Op4D, OpStream};
+
+/// How the values of a csv file are laid out.
+#[derive(Clone, Debug)]
+pub enum Layout {
+    /// Every row is a channel, e.g. the single row files in `./data`.
+    Rows,
+    /// Every column is a channel and every row is a sample. The header row, if
+    /// present, names the channels. The time column holds seconds.
+    Columns {
+        header: bool,
+        time_column: Option<usize>,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub enum Normalization {
+    None,
+    /// (value - mean) / standard deviation
+    ZScore,
+    /// Maps the range of the channel to 0..1
+    MinMax,
+    /// Multiplies every value, e.g. `Scale(2.0e14)` for raw MEG data
+    Scale(f64),
+}
+
+#[derive(Clone, Debug)]
+pub struct DataConfig {
+    pub paths: Vec<String>,
+    pub layout: Layout,
+    /// Samples per second, used when there is no time column.
+    pub sample_rate: f64,
+    pub normalization: Normalization,
+    /// Averages every n samples into one.
+    pub downsample: usize,
+    /// Only samples whose absolute normalized value reaches the threshold
+    /// become events.
+    pub threshold: Option<f64>,
+    /// Optional wav file to play alongside the data.
+    pub audio: Option<String>,
+}
+
+impl Default for DataConfig {
+    fn default() -> Self {
+        Self {
+            paths: vec![],
+            layout: Layout::Rows,
+            sample_rate: 1_000.0,
+            normalization: Normalization::ZScore,
+            downsample: 100,
+            threshold: None,
+            audio: None,
+        }
+    }
+}
+
+/// A named series of samples in seconds.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Channel {
+    pub name: String,
+    pub times: Vec<f64>,
+    pub values: Vec<f64>,
+}
+
+impl DataConfig {
+    pub fn op_streams(&self) -> Result<Vec<OpStream>, Error> {
+        let mut channels = vec![];
+        for path in self.paths.iter() {
+            channels.append(&mut self.read_channels(path)?);
+        }
+        let n_channels = channels.len();
+
+        let ops: Vec<Vec<Op4D>> = channels
+            .into_iter()
+            .enumerate()
+            .map(|(idx, mut channel)| {
+                channel.values = normalize(&channel.values, &self.normalization);
+                let channel = downsample(&channel, self.downsample);
+                channel_to_ops(&channel, idx, n_channels, self.threshold)
+            })
+            .collect();
+
+        let length = ops
+            .iter()
+            .filter_map(|ops| ops.last())
+            .map(|op| (op.t + op.l) as f32)
+            .fold(0.0, f32::max);
+
+        Ok(ops
+            .into_iter()
+            .filter(|ops| !ops.is_empty())
+            .map(|ops| OpStream {
+                names: ops[0].names.to_owned(),
+                ops,
+                length,
+            })
+            .collect())
+    }
+
+    fn read_channels(&self, path: &str) -> Result<Vec<Channel>, Error> {
+        let contents = std::fs::read_to_string(path)?;
+        let stem = Path::new(path)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.to_string());
+
+        match self.layout {
+            Layout::Rows => parse_rows(&contents, &stem, self.sample_rate),
+            Layout::Columns {
+                header,
+                time_column,
+            } => parse_columns(&contents, &stem, self.sample_rate, header, time_column),
+        }
+    }
+}
+
+fn parse_error(name: &str, line: usize, column: usize, msg: String) -> Error {
+    Error::with_msg(format!(
+        "{}, line {}, column {}: {}",
+        name,
+        line + 1,
+        column,
+        msg
+    ))
+}
+
+/// The values of a line by position, `line` is 0 based.
+fn parse_values(name: &str, line: usize, contents: &str) -> Result<Vec<f64>, Error> {
+    contents
+        .split(',')
+        .enumerate()
+        .map(|(column, value)| {
+            value.trim().parse::<f64>().map_err(|_| {
+                let msg = format!("{:?} is not a number", value.trim());
+                parse_error(name, line, column, msg)
+            })
+        })
+        .collect()
+}
+
+/// The non empty lines after the first `skip`, with their line numbers.
+fn parse_lines(contents: &str, name: &str, skip: usize) -> Result<Vec<(usize, Vec<f64>)>, Error> {
+    contents
+        .lines()
+        .enumerate()
+        .skip(skip)
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(idx, line)| Ok((idx, parse_values(name, idx, line)?)))
+        .collect()
+}
+
+fn sample_times(n: usize, sample_rate: f64) -> Vec<f64> {
+    (0..n).map(|idx| idx as f64 / sample_rate).collect()
+}
+
+pub fn parse_rows(contents: &str, name: &str, sample_rate: f64) -> Result<Vec<Channel>, Error> {
+    let rows = parse_lines(contents, name, 0)?;
+    let n_rows = rows.len();
+
+    Ok(rows
+        .into_iter()
+        .enumerate()
+        .map(|(idx, (_, values))| Channel {
+            name: if n_rows == 1 {
+                name.to_string()
+            } else {
+                format!("{}_{}", name, idx)
+            },
+            times: sample_times(values.len(), sample_rate),
+            values,
+        })
+        .collect())
+}
+
+pub fn parse_columns(
+    contents: &str,
+    name: &str,
+    sample_rate: f64,
+    header: bool,
+    time_column: Option<usize>,
+) -> Result<Vec<Channel>, Error> {
+    let names: Option<Vec<String>> = if header {
+        contents.lines().next().map(|line| {
+            line.split(',')
+                .map(|column| column.trim().to_string())
+                .collect()
+        })
+    } else {
+        None
+    };
+    let mut rows = parse_lines(contents, name, header as usize)?;
+
+    // Every row needs as many columns as the first, and the time column.
+    let n_columns = match (rows.first(), &names) {
+        (_, Some(names)) => names.len(),
+        (Some((_, row)), None) => row.len(),
+        (None, None) => 0,
+    };
+    let n_columns = n_columns.max(time_column.map_or(0, |column| column + 1));
+    if let Some((line, row)) = rows.iter().find(|(_, row)| row.len() < n_columns) {
+        let msg = format!("missing, expected {} columns", n_columns);
+        return Err(parse_error(name, *line, row.len(), msg));
+    }
+
+    // A time column isn't necessarily sorted, downsampling needs neighbouring
+    // samples to be neighbours in time.
+    if let Some(column) = time_column {
+        rows.sort_by(|(_, a), (_, b)| a[column].partial_cmp(&b[column]).unwrap_or(Ordering::Equal));
+    }
+    let times = match time_column {
+        Some(column) => rows.iter().map(|(_, row)| row[column]).collect(),
+        None => sample_times(rows.len(), sample_rate),
+    };
+
+    Ok((0..n_columns)
+        .filter(|column| Some(*column) != time_column)
+        .map(|column| Channel {
+            name: names
+                .as_ref()
+                .and_then(|names| names.get(column).cloned())
+                .unwrap_or_else(|| format!("{}_{}", name, column)),
+            times: times.to_owned(),
+            values: rows.iter().map(|(_, row)| row[column]).collect(),
+        })
+        .collect())
+}
+
+pub fn normalize(values: &[f64], normalization: &Normalization) -> Vec<f64> {
+    match normalization {
+        Normalization::None => values.to_vec(),
+        Normalization::Scale(scale) => values.iter().map(|v| v * scale).collect(),
+        Normalization::ZScore => {
+            let n = values.len().max(1) as f64;
+            let mean = values.iter().sum::<f64>() / n;
+            let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n;
+            let std_dev = variance.sqrt();
+            if std_dev == 0.0 {
+                values.iter().map(|_| 0.0).collect()
+            } else {
+                values.iter().map(|v| (v - mean) / std_dev).collect()
+            }
+        }
+        Normalization::MinMax => {
+            let min = values.iter().cloned().fold(f64::INFINITY, f64::min);
+            let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+            if max - min == 0.0 {
+                values.iter().map(|_| 0.0).collect()
+            } else {
+                values.iter().map(|v| (v - min) / (max - min)).collect()
+            }
+        }
+    }
+}
+
+pub fn downsample(channel: &Channel, n: usize) -> Channel {
+    if n <= 1 {
+        return channel.to_owned();
+    }
+    let mean = |chunk: &[f64]| chunk.iter().sum::<f64>() / chunk.len() as f64;
+
+    Channel {
+        name: channel.name.to_owned(),
+        times: channel.times.chunks(n).map(|chunk| chunk[0]).collect(),
+        values: channel.values.chunks(n).map(mean).collect(),
+    }
+}
+
+/// One `On` event per sample. y follows the value, x spreads the channels
+/// across the canvas and z is the magnitude. Channels are sorted by time.
+pub fn channel_to_ops(
+    channel: &Channel,
+    idx: usize,
+    n_channels: usize,
+    threshold: Option<f64>,
+) -> Vec<Op4D> {
+    let x = if n_channels > 1 {
+        idx as f64 / (n_channels - 1) as f64 * 2.0 - 1.0
+    } else {
+        0.0
+    };
+
+    channel
+        .times
+        .iter()
+        .zip(channel.values.iter())
+        .enumerate()
+        .filter(|(_, (_, value))| threshold.map_or(true, |threshold| value.abs() >= threshold))
+        .map(|(event, (t, value))| {
+            let next = channel.times.get(event + 1).unwrap_or(t);
+            Op4D {
+                t: *t,
+                voice: idx as _,
+                event: event as _,
+                event_type: EventType::On,
+                x,
+                y: *value,
+                z: value.abs(),
+                l: (next - t).max(0.0),
+                names: vec![channel.name.to_owned()],
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn test_normalize() {
+    let values = vec![1.0, 2.0, 3.0];
+    assert_eq!(
+        normalize(&values, &Normalization::MinMax),
+        vec![0.0, 0.5, 1.0]
+    );
+    assert_eq!(
+        normalize(&values, &Normalization::Scale(2.0)),
+        vec![2.0, 4.0, 6.0]
+    );
+    let z = normalize(&values, &Normalization::ZScore);
+    assert!(z[1].abs() < 1e-12);
+    assert!((z[0] + z[2]).abs() < 1e-12);
+}
+
+#[test]
+fn test_downsample_and_threshold() {
+    let channel = Channel {
+        name: "a".into(),
+        times: sample_times(6, 2.0),
+        values: vec![0.0, 2.0, 0.0, 0.0, 4.0, 4.0],
+    };
+    let downsampled = downsample(&channel, 2);
+    assert_eq!(downsampled.times, vec![0.0, 1.0, 2.0]);
+    assert_eq!(downsampled.values, vec![1.0, 0.0, 4.0]);
+
+    let ops = channel_to_ops(&downsampled, 0, 1, Some(0.5));
+    assert_eq!(ops.len(), 2);
+    assert_eq!(ops[1].t, 2.0);
+    assert_eq!(ops[1].names, vec!["a".to_string()]);
+}
+
+#[test]
+fn test_parse_columns_with_time_column() {
+    let contents = "t,a,b\n0.0,1.0,2.0\n0.5,3.0,4.0\n";
+    let channels = parse_columns(contents, "file", 1.0, true, Some(0)).unwrap();
+    assert_eq!(channels.len(), 2);
+    assert_eq!(channels[0].name, "a");
+    assert_eq!(channels[1].times, vec![0.0, 0.5]);
+    assert_eq!(channels[1].values, vec![2.0, 4.0]);
+}
+
+#[test]
+fn test_parse_columns_errors() {
+    let short = "t,a,b\n0.0,1.0,2.0\n0.5,3.0\n";
+    let e = parse_columns(short, "file", 1.0, true, Some(0)).unwrap_err();
+    assert!(format!("{:?}", e).contains("line 3, column 2"));
+
+    // A bad value doesn't shift the columns after it.
+    let invalid = "0.0,x,2.0\n";
+    let e = parse_columns(invalid, "file", 1.0, false, None).unwrap_err();
+    assert!(format!("{:?}", e).contains("line 1, column 1"));
+
+    assert!(parse_rows("1.0,,2.0\n", "file", 1.0).is_err());
+}
+
+#[test]
+fn test_ops_sorted_by_time() {
+    let contents = "1.0,3.0\n0.0,1.0\n0.5,2.0\n";
+    let channels = parse_columns(contents, "file", 1.0, false, Some(0)).unwrap();
+    let ops = channel_to_ops(&channels[0], 0, 1, None);
+    let times: Vec<f64> = ops.iter().map(|op| op.t).collect();
+    assert_eq!(times, vec![0.0, 0.5, 1.0]);
+    assert_eq!(ops[0].y, 1.0);
+    assert_eq!(ops[0].l, 0.5);
+}
+
+#[test]
+fn test_downsample_unsorted_time_column() {
+    let path = std::env::temp_dir().join(format!("kintaro-unsorted-{}.csv", std::process::id()));
+    std::fs::write(&path, "3.0,40.0\n0.0,10.0\n2.0,30.0\n1.0,20.0\n").unwrap();
+    let config = DataConfig {
+        paths: vec![path.to_string_lossy().to_string()],
+        layout: Layout::Columns {
+            header: false,
+            time_column: Some(0),
+        },
+        normalization: Normalization::None,
+        downsample: 2,
+        ..DataConfig::default()
+    };
+    let op_streams = config.op_streams().unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let ops = &op_streams[0].ops;
+    let samples: Vec<(f64, f64)> = ops.iter().map(|op| (op.t, op.y)).collect();
+    assert_eq!(samples, vec![(0.0, 15.0), (2.0, 35.0)]);
+    assert_eq!(op_streams[0].length, 2.0);
+}
@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+use std::f32::consts::PI;
+
+/// Easing functions mapping progress in 0..1 to 0..1.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Easing {
+    Linear,
+    InQuad,
+    OutQuad,
+    InOutQuad,
+    InCubic,
+    OutCubic,
+    InOutCubic,
+    InOutSine,
+    OutExpo,
+}
+
+impl Default for Easing {
+    fn default() -> Self {
+        Easing::Linear
+    }
+}
+
+impl Easing {
+    pub fn ease(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::InQuad => t * t,
+            Easing::OutQuad => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::InOutQuad => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+                }
+            }
+            Easing::InCubic => t * t * t,
+            Easing::OutCubic => 1.0 - (1.0 - t).powi(3),
+            Easing::InOutCubic => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+            Easing::InOutSine => -((PI * t).cos() - 1.0) / 2.0,
+            Easing::OutExpo => {
+                if t >= 1.0 {
+                    1.0
+                } else {
+                    1.0 - 2.0_f32.powf(-10.0 * t)
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_easing_endpoints() {
+    let easings = [
+        Easing::Linear,
+        Easing::InQuad,
+        Easing::OutQuad,
+        Easing::InOutQuad,
+        Easing::InCubic,
+        Easing::OutCubic,
+        Easing::InOutCubic,
+        Easing::InOutSine,
+        Easing::OutExpo,
+    ];
+    for easing in easings.iter() {
+        assert!(easing.ease(0.0).abs() < 1e-6, "{:?}", easing);
+        assert!((easing.ease(1.0) - 1.0).abs() < 1e-6, "{:?}", easing);
+    }
+    assert_eq!(Easing::InOutQuad.ease(0.5), 0.5);
+}
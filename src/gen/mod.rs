@@ -5,21 +5,22 @@ pub type Index = u16;
 use crate::{
     color::Color,
     op_stream::OpStream,
+    rng::GenRng,
     vertex::{shape::Position, Vertex},
 };
 
 pub trait GenColor: dyn_clone::DynClone + Debug {
-    fn gen(&self, op_stream: &OpStream) -> Color;
+    fn gen(&self, op_stream: &OpStream, rng: &mut GenRng) -> Color;
     fn update(&mut self);
 }
 pub trait GenPosition: dyn_clone::DynClone + Debug {
-    fn gen(&self) -> Position;
+    fn gen(&self, rng: &mut GenRng) -> Position;
 }
 pub trait GenVertex: dyn_clone::DynClone + Debug {
-    fn gen(&self) -> Vec<Vertex>;
+    fn gen(&self, rng: &mut GenRng) -> Vec<Vertex>;
 }
 pub trait GenIndex: dyn_clone::DynClone + Debug {
-    fn gen(&self, n_vertices: usize) -> Index;
+    fn gen(&self, n_vertices: usize, rng: &mut GenRng) -> Index;
 }
 
 dyn_clone::clone_trait_object!(GenColor);
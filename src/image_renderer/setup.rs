@@ -1,3 +1,5 @@
+use crate::shared::{request_device, DevicePolicy};
+use weresocool::error::Error;
 use winit::window::Window;
 
 pub struct ImageSetup {
@@ -8,45 +10,29 @@ pub struct ImageSetup {
     pub config: wgpu::SurfaceConfiguration,
 }
 
-pub async fn setup(window: &Window) -> ImageSetup {
+pub async fn setup(window: &Window, policy: &DevicePolicy) -> Result<ImageSetup, Error> {
     let size = window.inner_size();
 
-    let instance = wgpu::Instance::new(wgpu::Backends::all());
+    let instance = wgpu::Instance::new(policy.backends);
     let surface = unsafe { instance.create_surface(window) };
-    let adapter = instance
-        .request_adapter(&wgpu::RequestAdapterOptions {
-            power_preference: wgpu::PowerPreference::default(),
-            compatible_surface: Some(&surface),
-            force_fallback_adapter: false,
-        })
-        .await
-        .unwrap();
-    let (device, queue) = adapter
-        .request_device(
-            &wgpu::DeviceDescriptor {
-                label: None,
-                features: wgpu::Features::empty(),
-                limits: wgpu::Limits::default(),
-            },
-            None, // Trace path
-        )
-        .await
-        .unwrap();
+    let (adapter, device, queue) = request_device(&instance, policy, Some(&surface)).await?;
 
     let config = wgpu::SurfaceConfiguration {
         usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-        format: surface.get_preferred_format(&adapter).unwrap(),
+        format: surface.get_preferred_format(&adapter).ok_or_else(|| {
+            Error::with_msg("Surface is incompatible with the adapter".to_string())
+        })?,
         width: size.width,
         height: size.height,
         present_mode: wgpu::PresentMode::Fifo,
     };
     surface.configure(&device, &config);
 
-    ImageSetup {
+    Ok(ImageSetup {
         device,
         queue,
         size,
         surface,
         config,
-    }
+    })
 }
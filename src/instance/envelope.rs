@@ -0,0 +1,118 @@
+use serde::{Deserialize, Serialize};
+
+use crate::easing::Easing;
+
+/// Attack, decay, sustain and release, in seconds, with the sustain level in
+/// 0..1. Every stage is shaped by the same easing.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Adsr {
+    pub attack: f32,
+    pub decay: f32,
+    pub sustain: f32,
+    pub release: f32,
+    pub easing: Easing,
+}
+
+impl Adsr {
+    /// The level while the note is held.
+    fn held(&self, age: f32) -> f32 {
+        if age < self.attack {
+            self.easing.ease(age / self.attack)
+        } else if age < self.attack + self.decay {
+            let t = self.easing.ease((age - self.attack) / self.decay);
+            1.0 + (self.sustain - 1.0) * t
+        } else {
+            self.sustain
+        }
+    }
+
+    pub fn value(&self, age: f32, release_age: Option<f32>) -> f32 {
+        match release_age {
+            None => self.held(age),
+            Some(release_age) => {
+                let level = self.held(release_age);
+                let since = age - release_age;
+                if since >= self.release {
+                    0.0
+                } else {
+                    level * (1.0 - self.easing.ease(since / self.release))
+                }
+            }
+        }
+    }
+
+    pub fn is_finished(&self, age: f32, release_age: Option<f32>) -> bool {
+        release_age.map_or(false, |release_age| age - release_age >= self.release)
+    }
+}
+
+/// Separate envelopes for size, alpha and color intensity. `None` leaves the
+/// value at 1.0 so the instance's life alone shapes it.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Envelope {
+    pub size: Option<Adsr>,
+    pub alpha: Option<Adsr>,
+    pub intensity: Option<Adsr>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct EnvelopeValues {
+    pub size: f32,
+    pub alpha: f32,
+    pub intensity: f32,
+}
+
+impl Envelope {
+    pub fn values(&self, age: f32, release_age: Option<f32>) -> EnvelopeValues {
+        let value = |adsr: &Option<Adsr>| adsr.map_or(1.0, |adsr| adsr.value(age, release_age));
+        EnvelopeValues {
+            size: value(&self.size),
+            alpha: value(&self.alpha),
+            intensity: value(&self.intensity),
+        }
+    }
+
+    /// True once every envelope has finished its release. An instance without
+    /// envelopes is never finished by its envelope.
+    pub fn is_finished(&self, age: f32, release_age: Option<f32>) -> bool {
+        let envelopes: Vec<&Adsr> = [&self.size, &self.alpha, &self.intensity]
+            .iter()
+            .filter_map(|adsr| adsr.as_ref())
+            .collect();
+        !envelopes.is_empty()
+            && envelopes
+                .iter()
+                .all(|adsr| adsr.is_finished(age, release_age))
+    }
+}
+
+#[test]
+fn test_adsr_stages() {
+    let adsr = Adsr {
+        attack: 1.0,
+        decay: 1.0,
+        sustain: 0.5,
+        release: 2.0,
+        easing: Easing::Linear,
+    };
+    assert_eq!(adsr.value(0.0, None), 0.0);
+    assert_eq!(adsr.value(0.5, None), 0.5);
+    assert_eq!(adsr.value(1.5, None), 0.75);
+    assert_eq!(adsr.value(10.0, None), 0.5);
+    assert_eq!(adsr.value(11.0, Some(10.0)), 0.25);
+    assert_eq!(adsr.value(12.0, Some(10.0)), 0.0);
+    // released during the attack, the release starts from the current level
+    assert_eq!(adsr.value(0.5, Some(0.5)), 0.5);
+    assert!(adsr.is_finished(12.0, Some(10.0)));
+    assert!(!adsr.is_finished(12.0, None));
+}
+
+#[test]
+fn test_envelope_defaults_to_flat() {
+    let envelope = Envelope::default();
+    let values = envelope.values(3.0, Some(1.0));
+    assert_eq!(values.size, 1.0);
+    assert_eq!(values.alpha, 1.0);
+    assert_eq!(values.intensity, 1.0);
+    assert!(!envelope.is_finished(100.0, Some(1.0)));
+}
@@ -1,6 +1,8 @@
+use super::envelope::Envelope;
 use super::Instance;
 use crate::canvas::Canvas;
 use crate::op_stream::Op4D;
+use crate::rng::GenRng;
 use cgmath::Rotation3;
 use cgmath::Vector3;
 use kintaro_egui_lib::InstanceMul;
@@ -9,7 +11,11 @@ use std::fmt::Debug;
 
 pub trait Instancer: dyn_clone::DynClone + Debug {
     fn update_instance(&self, instance: &mut Instance, dt: f32);
-    fn op4d_to_instance_transformation(&self, input: InstancerInput) -> InstancerOutput;
+    fn op4d_to_instance_transformation(
+        &self,
+        input: InstancerInput,
+        rng: &mut GenRng,
+    ) -> InstancerOutput;
 }
 dyn_clone::clone_trait_object!(Instancer);
 
@@ -51,8 +57,11 @@ impl Instancer for SimpleInstancer {
         // );
     }
 
-    fn op4d_to_instance_transformation(&self, input: InstancerInput) -> InstancerOutput {
-        let mut rng = rand::thread_rng();
+    fn op4d_to_instance_transformation(
+        &self,
+        input: InstancerInput,
+        rng: &mut GenRng,
+    ) -> InstancerOutput {
         let rotation = cgmath::Quaternion::from_axis_angle(
             cgmath::Vector3::unit_x(),
             cgmath::Deg(rng.gen_range(-0.3..0.3)),
@@ -82,6 +91,13 @@ pub fn op4d_to_instance(input: InstancerOutput, op4d: Op4D, canvas: &Canvas) ->
         size: input.size,
         length: input.length,
         names: op4d.names.to_owned(),
+        voice: op4d.voice,
+        event: op4d.event,
+        held: true,
+        age: 0.0,
+        hold: op4d.l as f32,
+        release_age: None,
+        envelope: Envelope::default(),
     }
 }
 
@@ -1,10 +1,13 @@
+pub mod envelope;
 pub mod instancer;
 pub mod raw;
-use cgmath::Rotation3;
+use cgmath::{EuclideanSpace, Rotation3};
 use rand::Rng;
 use wgpu::util::DeviceExt;
 
+use self::envelope::Envelope;
 use self::raw::InstanceRaw;
+use crate::op_stream::Op4D;
 
 #[derive(Clone, Debug)]
 pub struct Instance {
@@ -14,6 +17,19 @@ pub struct Instance {
     pub size: f32,
     pub length: f32,
     pub names: Vec<String>,
+    /// The op's voice and event, to match its `EventType::Off`.
+    pub voice: usize,
+    pub event: usize,
+    /// Whether the note is still sounding. Envelopes sustain until it is
+    /// released.
+    pub held: bool,
+    /// Seconds since the note started.
+    pub age: f32,
+    /// The note length, used to release notes without an `Off` event.
+    pub hold: f32,
+    /// The age at which the note was released.
+    pub release_age: Option<f32>,
+    pub envelope: Envelope,
 }
 
 pub fn make_instances(n: usize, size: (u32, u32)) -> Vec<Instance> {
@@ -49,11 +65,31 @@ pub fn make_instances(n: usize, size: (u32, u32)) -> Vec<Instance> {
                 size,
                 length: 1.0,
                 names: vec![],
+                voice: 0,
+                event: 0,
+                held: false,
+                age: 0.0,
+                hold: 0.0,
+                release_age: None,
+                envelope: Envelope::default(),
             }
         })
         .collect::<Vec<_>>()
 }
 
+/// The mean position of the instances, `None` when there are none.
+pub fn centroid(instances: &[Instance]) -> Option<cgmath::Point3<f32>> {
+    if instances.is_empty() {
+        return None;
+    }
+    let sum = instances
+        .iter()
+        .fold(cgmath::Vector3::new(0.0, 0.0, 0.0), |sum, instance| {
+            sum + instance.position
+        });
+    Some(cgmath::Point3::from_vec(sum / instances.len() as f32))
+}
+
 pub fn make_instance_buffer(
     instances: &Vec<Instance>,
     _size: (u32, u32),
@@ -81,6 +117,7 @@ pub fn make_instances_and_instance_buffer(
 
 impl Instance {
     pub fn to_raw(&self) -> InstanceRaw {
+        let envelope = self.envelope.values(self.age, self.release_age);
         InstanceRaw {
             model: (cgmath::Matrix4::from_translation(self.position)
                 * cgmath::Matrix4::from(self.rotation))
@@ -88,9 +125,27 @@ impl Instance {
             life: self.life,
             size: self.size,
             length: self.length,
+            envelope_size: envelope.size,
+            alpha: envelope.alpha,
+            intensity: envelope.intensity,
+        }
+    }
+
+    pub fn release(&mut self) {
+        if self.held {
+            self.held = false;
+            self.release_age = Some(self.age);
         }
     }
 
+    pub fn is_alive(&self) -> bool {
+        self.life > 0.0 && !self.envelope.is_finished(self.age, self.release_age)
+    }
+
+    pub fn is_op(&self, op: &Op4D) -> bool {
+        self.voice == op.voice && self.event == op.event
+    }
+
     pub fn update_state(&mut self, dt: f32) {
         self.life -= dt * 0.1;
         // self.position.y += f32::sin(3.0 * (2.0 - self.life));
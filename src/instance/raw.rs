@@ -5,6 +5,9 @@ pub struct InstanceRaw {
     pub(super) life: f32,
     pub(super) size: f32,
     pub(super) length: f32,
+    pub(super) envelope_size: f32,
+    pub(super) alpha: f32,
+    pub(super) intensity: f32,
 }
 
 impl InstanceRaw {
@@ -55,6 +58,21 @@ impl InstanceRaw {
                     shader_location: 11,
                     format: wgpu::VertexFormat::Float32,
                 },
+                wgpu::VertexAttribute {
+                    offset: mem::size_of::<[f32; 19]>() as wgpu::BufferAddress,
+                    shader_location: 12,
+                    format: wgpu::VertexFormat::Float32,
+                },
+                wgpu::VertexAttribute {
+                    offset: mem::size_of::<[f32; 20]>() as wgpu::BufferAddress,
+                    shader_location: 13,
+                    format: wgpu::VertexFormat::Float32,
+                },
+                wgpu::VertexAttribute {
+                    offset: mem::size_of::<[f32; 21]>() as wgpu::BufferAddress,
+                    shader_location: 14,
+                    format: wgpu::VertexFormat::Float32,
+                },
             ],
         }
     }
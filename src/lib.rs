@@ -7,19 +7,31 @@ pub mod clock;
 pub mod color;
 pub mod composition;
 pub mod config;
+pub mod data;
+pub mod easing;
 pub mod gen;
 pub mod instance;
+pub mod midi;
 pub mod op_stream;
+pub mod osc;
 pub mod print;
 pub mod realtime;
+pub mod remote;
+pub mod rng;
 pub mod save;
 pub mod shader;
 pub mod shared;
+pub mod stream_map;
 pub mod toy;
 pub mod uniforms;
 pub mod vertex;
 
 pub use crate::camera::default::default_cameras;
+pub use crate::camera::mode::CameraMode;
+pub use crate::camera::path::{CameraPath, Keyframe};
+pub use crate::camera::presets::{CameraPreset, CameraPresets};
+pub use crate::camera::transition::{CameraCue, TransitionConfig};
+pub use crate::camera::Movement;
 pub use crate::color::{
     color_map_from_named_colorsets,
     color_map_from_named_gen_color,
@@ -33,13 +45,26 @@ pub use crate::color::{
     RandColor,
     RandColorSet,
 };
-pub use crate::config::{CameraConfig, Config};
+pub use crate::config::{CameraConfig, Config, Source};
+pub use crate::data::{DataConfig, Layout, Normalization};
+pub use crate::easing::Easing;
 pub use crate::gen::*;
 pub use crate::instance::{
-    instancer::{Instancer, InstancerInput, InstancerOutput},
+    envelope::{Adsr, Envelope},
+    instancer::{Instancer, InstancerInput, InstancerOutput, SimpleInstancer},
     Instance,
 };
+pub use crate::midi::{MidiBinding, MidiInput, MidiInputKey, MidiMapping};
+pub use crate::op_stream::transform::{
+    Curve, Field, FilterEventType, FilterNames, FilterRange, OpTransform, Quantize, Remap, Thin,
+    TimeScale, TimeShift,
+};
+pub use crate::osc::{OscConfig, OscOutput, OscServer};
+pub use crate::realtime::bindings::{KeyAction, KeyBindings};
+pub use crate::remote::{RemoteEdit, RemoteServer, RemoteState};
+pub use crate::rng::GenRng;
 pub use crate::save::ConfigState;
+pub use crate::stream_map::StreamMap;
 pub use crate::vertex::shape::{RandIndex, RandPosition, Shape};
-pub use kintaro_egui_lib::InstanceMul;
+pub use kintaro_egui_lib::{CameraReactivity, InstanceMul, ProjectionConfig, UiControl};
 pub use weresocool::error::Error;
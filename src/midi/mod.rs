@@ -0,0 +1,328 @@
+use kintaro_egui_lib::{UiControl, UiState};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use weresocool::error::Error;
+
+/// A channel voice message. Channels are 0..16.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum MidiMessage {
+    ControlChange {
+        channel: u8,
+        controller: u8,
+        value: u8,
+    },
+    NoteOn {
+        channel: u8,
+        note: u8,
+        velocity: u8,
+    },
+    NoteOff {
+        channel: u8,
+        note: u8,
+    },
+}
+
+impl MidiMessage {
+    /// Parses the messages that can be bound, ignoring everything else.
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        let (status, data) = bytes.split_first()?;
+        let channel = status & 0x0f;
+        match (status & 0xf0, data) {
+            (0xb0, [controller, value, ..]) => Some(MidiMessage::ControlChange {
+                channel,
+                controller: *controller,
+                value: *value,
+            }),
+            // Note on with velocity 0 is a note off.
+            (0x90, [note, 0, ..]) | (0x80, [note, _, ..]) => Some(MidiMessage::NoteOff {
+                channel,
+                note: *note,
+            }),
+            (0x90, [note, velocity, ..]) => Some(MidiMessage::NoteOn {
+                channel,
+                note: *note,
+                velocity: *velocity,
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn input(&self) -> MidiInputKey {
+        match *self {
+            MidiMessage::ControlChange {
+                channel,
+                controller,
+                ..
+            } => MidiInputKey::Cc {
+                channel,
+                controller,
+            },
+            MidiMessage::NoteOn { channel, note, .. } | MidiMessage::NoteOff { channel, note } => {
+                MidiInputKey::Note { channel, note }
+            }
+        }
+    }
+
+    /// The message's value in 0..1.
+    pub fn value(&self) -> f32 {
+        match *self {
+            MidiMessage::ControlChange { value, .. } => value as f32 / 127.0,
+            MidiMessage::NoteOn { velocity, .. } => velocity as f32 / 127.0,
+            MidiMessage::NoteOff { .. } => 0.0,
+        }
+    }
+}
+
+/// A fader, knob or pad of a controller.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum MidiInputKey {
+    Cc { channel: u8, controller: u8 },
+    Note { channel: u8, note: u8 },
+}
+
+impl std::fmt::Display for MidiInputKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MidiInputKey::Cc {
+                channel,
+                controller,
+            } => write!(f, "ch {} cc {}", channel + 1, controller),
+            MidiInputKey::Note { channel, note } => write!(f, "ch {} note {}", channel + 1, note),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MidiBinding {
+    pub input: MidiInputKey,
+    pub control: UiControl,
+}
+
+/// Which inputs drive which controls, saved to `./save/midi.json`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct MidiMapping {
+    pub bindings: Vec<MidiBinding>,
+    /// Where edits are saved, `None` keeps them in memory.
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
+}
+
+impl MidiMapping {
+    pub const PATH: &'static str = "./save/midi.json";
+
+    /// Loads the mapping at `path`, keeping `self` when there is none yet.
+    /// Later edits are saved to `path` either way.
+    pub fn load(self, path: &Path) -> Result<Self, Error> {
+        let mut mapping = if path.is_file() {
+            println!("Loading midi mapping from {}", path.display());
+            let data = std::fs::read_to_string(path)?;
+            serde_json::from_str::<MidiMapping>(&data).map_err(|e| {
+                Error::with_msg(format!("Unable to parse {}: {}", path.display(), e))
+            })?
+        } else {
+            self
+        };
+        mapping.path = Some(path.to_path_buf());
+        Ok(mapping)
+    }
+
+    pub fn save(&self) -> Result<(), Error> {
+        if let Some(path) = &self.path {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let serialized = serde_json::to_string_pretty(self).map_err(|e| {
+                Error::with_msg(format!("Unable to serialize {}: {}", path.display(), e))
+            })?;
+            std::fs::write(path, serialized)?;
+        }
+        Ok(())
+    }
+
+    /// Binds an input, replacing what it was bound to before.
+    pub fn bind(&mut self, input: MidiInputKey, control: UiControl) {
+        self.bindings.retain(|binding| binding.input != input);
+        self.bindings.push(MidiBinding { input, control });
+    }
+
+    pub fn remove(&mut self, idx: usize) {
+        if idx < self.bindings.len() {
+            self.bindings.remove(idx);
+        }
+    }
+
+    pub fn control(&self, input: MidiInputKey) -> Option<&UiControl> {
+        self.bindings
+            .iter()
+            .find(|binding| binding.input == input)
+            .map(|binding| &binding.control)
+    }
+
+    pub fn descriptions(&self, state: &UiState) -> Vec<String> {
+        self.bindings
+            .iter()
+            .map(|binding| format!("{} -> {}", binding.input, binding.control.name(state)))
+            .collect()
+    }
+
+    /// Binds the message while learning, otherwise applies its control.
+    /// Returns whether the mapping changed.
+    pub fn handle(&mut self, message: MidiMessage, state: &mut UiState) -> bool {
+        let input = message.input();
+        if state.midi.learning {
+            // Releases of the pad that was just learned shouldn't bind.
+            if let (Some(control), false) = (
+                state.midi.control.clone(),
+                matches!(message, MidiMessage::NoteOff { .. }),
+            ) {
+                self.bind(input, control);
+                state.midi.learning = false;
+                return true;
+            }
+            return false;
+        }
+
+        if let Some(control) = self.control(input) {
+            // Buttons act on press only, a release would toggle them back. A
+            // pad is pressed however softly, only ccs need at least 0.5.
+            match message {
+                MidiMessage::NoteOff { .. } if control.is_button() => {}
+                MidiMessage::NoteOn { .. } if control.is_button() => {
+                    state.apply_control(control, 1.0)
+                }
+                _ => state.apply_control(control, message.value()),
+            }
+        }
+        false
+    }
+
+    /// Applies the pending messages and GUI edits, saving the mapping when it
+    /// changed.
+    pub fn update(&mut self, input: &MidiInput, state: &mut UiState) -> Result<(), Error> {
+        let mut changed = false;
+        if let Some(idx) = state.midi.remove.take() {
+            self.remove(idx);
+            changed = true;
+        }
+        input.try_iter().for_each(|message| {
+            changed |= self.handle(message, state);
+        });
+        state.midi.connected = true;
+        if changed || state.midi.bindings.len() != self.bindings.len() {
+            state.midi.bindings = self.descriptions(state);
+        }
+        if changed {
+            self.save()?;
+        }
+        Ok(())
+    }
+}
+
+/// Raw messages from a MIDI port or, for tests and other transports, from
+/// the sender of an in-memory channel.
+pub struct MidiInput {
+    receiver: Receiver<Vec<u8>>,
+    #[cfg(feature = "midi")]
+    _connection: Option<midir::MidiInputConnection<()>>,
+}
+
+impl MidiInput {
+    pub fn channel() -> (Sender<Vec<u8>>, Self) {
+        let (sender, receiver) = channel();
+        (
+            sender,
+            Self {
+                receiver,
+                #[cfg(feature = "midi")]
+                _connection: None,
+            },
+        )
+    }
+
+    /// Connects to the first port whose name contains `port`, case
+    /// insensitive, or to the first port.
+    #[cfg(feature = "midi")]
+    pub fn connect(port: Option<&str>) -> Result<Self, Error> {
+        let midi_in = midir::MidiInput::new("kintaro")
+            .map_err(|e| Error::with_msg(format!("Unable to open midi input: {}", e)))?;
+        let ports = midi_in.ports();
+        let found = ports.iter().find(|p| {
+            let name = midi_in.port_name(p).unwrap_or_default().to_lowercase();
+            port.map_or(true, |port| name.contains(&port.to_lowercase()))
+        });
+        let found = found
+            .ok_or_else(|| Error::with_msg(format!("No midi input port matching {:?}", port)))?;
+        let name = midi_in.port_name(found).unwrap_or_default();
+
+        let (sender, mut input) = Self::channel();
+        let connection = midi_in
+            .connect(
+                found,
+                "kintaro-input",
+                move |_stamp, bytes, _| {
+                    sender.send(bytes.to_vec()).ok();
+                },
+                (),
+            )
+            .map_err(|e| Error::with_msg(format!("Unable to connect to {}: {}", name, e)))?;
+        println!("midi input: {}", name);
+        input._connection = Some(connection);
+        Ok(input)
+    }
+
+    pub fn try_iter(&self) -> impl Iterator<Item = MidiMessage> + '_ {
+        self.receiver
+            .try_iter()
+            .filter_map(|bytes| MidiMessage::parse(&bytes))
+    }
+}
+
+#[test]
+fn test_learn_and_apply_from_in_memory_source() {
+    let (sender, input) = MidiInput::channel();
+    let mut mapping = MidiMapping::default();
+    let mut state = UiState {
+        streams: vec![kintaro_egui_lib::StreamState::init("a".into(), None)],
+        ..UiState::default()
+    };
+
+    // Learn a fader for the volume and a pad for muting the first stream.
+    state.midi.control = Some(UiControl::Volume);
+    state.midi.learning = true;
+    sender.send(vec![0xb1, 7, 0]).unwrap();
+    mapping.update(&input, &mut state).unwrap();
+    assert!(!state.midi.learning);
+
+    state.midi.control = Some(UiControl::Mute("a".into()));
+    state.midi.learning = true;
+    sender.send(vec![0x90, 36, 100]).unwrap();
+    sender.send(vec![0x80, 36, 0]).unwrap();
+    mapping.update(&input, &mut state).unwrap();
+    assert_eq!(
+        state.midi.bindings,
+        vec!["ch 2 cc 7 -> volume", "ch 1 note 36 -> mute a"]
+    );
+
+    sender.send(vec![0xb1, 7, 127]).unwrap();
+    // Soft hits are still presses.
+    sender.send(vec![0x90, 36, 20]).unwrap();
+    sender.send(vec![0x90, 36, 0]).unwrap();
+    // Unbound and system messages are ignored.
+    sender.send(vec![0xb0, 7, 0]).unwrap();
+    sender.send(vec![0xf8]).unwrap();
+    mapping.update(&input, &mut state).unwrap();
+    assert_eq!(state.volume, 1.0);
+    assert!(state.streams[0].mute);
+
+    state.midi.remove = Some(0);
+    mapping.update(&input, &mut state).unwrap();
+    assert_eq!(
+        mapping.control(MidiInputKey::Cc {
+            channel: 1,
+            controller: 7
+        }),
+        None
+    );
+    assert_eq!(state.midi.bindings.len(), 1);
+}
@@ -1,10 +1,15 @@
 pub mod renderpasses;
+pub mod transform;
+use crate::instance::envelope::Envelope;
 use crate::instance::Instance;
+use crate::op_stream::transform::OpTransform;
 use cgmath::{Rotation3, Vector3};
 use kintaro_egui_lib::InstanceMul;
 use rand::Rng;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use weresocool::error::Error;
 pub use weresocool::generation::json::{EventType, Op4D};
 use weresocool::generation::parsed_to_render::AudioVisual;
 
@@ -16,36 +21,55 @@ pub struct OpStream {
 }
 
 impl OpStream {
-    pub fn from_json(filename: &str) -> Vec<OpStream> {
-        let data = std::fs::read_to_string(format!("./{}.socool.json", filename))
-            .expect("Unable to read file");
+    /// Where the op streams of a composition are exported to, e.g.
+    /// `kintaro.socool.json` for `kintaro.socool`.
+    pub fn json_path(composition: &str) -> PathBuf {
+        Path::new(composition).with_extension("socool.json")
+    }
 
-        let deserialized: OpStream = serde_json::from_str(&data).unwrap();
-        let mut op_streams = BTreeMap::<Vec<String>, Vec<Op4D>>::new();
-        deserialized.ops.iter().for_each(|op| {
-            if op.names.is_empty() {
-                let stream = op_streams.entry(vec!["nameless".into()]).or_insert(vec![]);
-                stream.push(op.clone());
-            } else {
-                let names = &op.names;
-                let stream = op_streams.entry(names.to_owned()).or_insert(vec![]);
-                stream.push(op.clone());
-            }
-        });
+    /// Reads the `json_path` of a composition, a single `OpStream` holding
+    /// every op of a piece, and splits it by name.
+    pub fn from_json(composition: &str) -> Result<Vec<OpStream>, Error> {
+        let path = Self::json_path(composition);
+        let data = std::fs::read_to_string(&path)?;
+        let deserialized: OpStream = serde_json::from_str(&data)
+            .map_err(|e| Error::with_msg(format!("Unable to parse {}: {}", path.display(), e)))?;
 
-        op_streams
-            .into_iter()
-            .map(|(names, ops)| OpStream {
-                ops,
-                length: deserialized.length,
-                names,
-            })
-            .collect()
+        Ok(OpStream::from_ops(&deserialized.ops, deserialized.length))
+    }
+
+    /// Writes all of the op streams back into the single `json_path` of a
+    /// composition.
+    pub fn to_json(op_streams: &[OpStream], composition: &str) -> Result<(), Error> {
+        let path = Self::json_path(composition);
+        let mut ops: Vec<Op4D> = op_streams
+            .iter()
+            .flat_map(|op_stream| op_stream.ops.to_owned())
+            .collect();
+        ops.sort_by(|a, b| a.t.partial_cmp(&b.t).unwrap_or(std::cmp::Ordering::Equal));
+        let combined = OpStream {
+            ops,
+            length: op_streams
+                .iter()
+                .map(|op_stream| op_stream.length)
+                .fold(0.0, f32::max),
+            names: vec![],
+        };
+        let serialized = serde_json::to_string(&combined).map_err(|e| {
+            Error::with_msg(format!("Unable to serialize {}: {}", path.display(), e))
+        })?;
+        std::fs::write(&path, serialized)?;
+        println!("Op streams written: {}", path.display());
+        Ok(())
     }
 
     pub fn from_vec_op4d(av: &AudioVisual) -> Vec<OpStream> {
+        OpStream::from_ops(&av.visual, av.length)
+    }
+
+    pub fn from_ops(ops: &[Op4D], length: f32) -> Vec<OpStream> {
         let mut op_streams = BTreeMap::<Vec<String>, Vec<Op4D>>::new();
-        av.visual.iter().for_each(|op| {
+        ops.iter().for_each(|op| {
             if op.names.is_empty() {
                 let stream = op_streams.entry(vec!["nameless".into()]).or_insert(vec![]);
                 stream.push(op.clone());
@@ -58,13 +82,27 @@ impl OpStream {
 
         op_streams
             .into_iter()
-            .map(|(names, ops)| OpStream {
-                ops,
-                length: av.length,
-                names,
-            })
+            .map(|(names, ops)| OpStream { ops, length, names })
             .collect()
     }
+
+    /// Applies the transforms and recomputes the length, since time
+    /// transforms can move ops past the end of the piece.
+    pub fn transform(&mut self, transforms: &[Box<dyn OpTransform>]) {
+        if !transforms.is_empty() {
+            self.ops = transform::apply_transforms(std::mem::take(&mut self.ops), transforms);
+            self.length = self
+                .ops
+                .iter()
+                .map(|op| (op.t + op.l) as f32)
+                .fold(0.0, f32::max);
+        }
+    }
+
+    pub fn name(&self) -> String {
+        self.names.join(", ")
+    }
+
     pub fn get_batch(&mut self, t: f32) -> Vec<Op4D> {
         let result: Vec<Op4D> = self
             .ops
@@ -171,6 +209,13 @@ impl ToInstance for Op4D {
             size,
             length,
             names: self.names.to_owned(),
+            voice: self.voice,
+            event: self.event,
+            held: true,
+            age: 0.0,
+            hold: self.l as f32,
+            release_age: None,
+            envelope: Envelope::default(),
         }
     }
 }
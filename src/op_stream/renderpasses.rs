@@ -1,4 +1,5 @@
 use crate::instance::make_instances_and_instance_buffer;
+use crate::rng::stream_rng;
 use crate::shared::create_render_pipeline;
 use crate::vertex::shape::ShapeGenResult;
 use crate::vertex::{create_index_buffer, create_vertex_buffer};
@@ -14,10 +15,17 @@ pub fn make_renderpasses(
     format: wgpu::TextureFormat,
 ) -> Vec<RenderPassInput> {
     op_streams
-        .iter()
-        .map(|op_stream| {
-            let ShapeGenResult { vertices, indices } = config.shape.gen(&op_stream);
-            config.shape.update();
+        .into_iter()
+        .map(|mut op_stream| {
+            op_stream.transform(config.transforms.get(&op_stream));
+            let shape = config.shapes.get_mut(&op_stream);
+            let mut rng = stream_rng(config.seed, &op_stream.name());
+            let ShapeGenResult { vertices, indices } = shape.gen(&op_stream, &mut rng);
+            shape.update();
+            let shape = shape.clone();
+            let instancer = config.instancers.get(&op_stream).clone();
+            let envelope = *config.envelopes.get(&op_stream);
+            let instance_mul = config.stream_instance_muls.get(&op_stream.name()).copied();
             let (instances, instance_buffer) =
                 make_instances_and_instance_buffer(0, config.window_size, &device);
             let (uniforms, uniform_buffer, uniform_bind_group_layout, uniform_bind_group) =
@@ -29,13 +37,18 @@ pub fn make_renderpasses(
                 vertex_buffer: create_vertex_buffer(&device, &vertices.as_slice()),
                 index_buffer: create_index_buffer(&device, &indices.as_slice()),
                 vertices: vertices.into(),
-                op_stream: op_stream.to_owned(),
+                op_stream,
                 uniform_bind_group,
                 instances,
                 instance_buffer,
                 uniform_buffer,
                 uniforms,
-                shape: config.shape.clone(),
+                shape,
+                instancer,
+                instance_mul,
+                envelope,
+                rng,
+                visible: true,
                 render_pipeline,
             }
         })
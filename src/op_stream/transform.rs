@@ -0,0 +1,312 @@
+use std::fmt::Debug;
+use std::mem::discriminant;
+
+use super::{EventType, Op4D};
+
+/// A stage applied to the ops of a stream before they become instances.
+pub trait OpTransform: dyn_clone::DynClone + Debug {
+    fn apply(&self, ops: Vec<Op4D>) -> Vec<Op4D>;
+}
+dyn_clone::clone_trait_object!(OpTransform);
+
+/// Applies each transform in order and restores time order, since time
+/// transforms can move ops past each other.
+pub fn apply_transforms(ops: Vec<Op4D>, transforms: &[Box<dyn OpTransform>]) -> Vec<Op4D> {
+    let mut ops = transforms
+        .iter()
+        .fold(ops, |ops, transform| transform.apply(ops));
+    ops.sort_by(|a, b| a.t.partial_cmp(&b.t).unwrap_or(std::cmp::Ordering::Equal));
+    ops
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Field {
+    T,
+    X,
+    Y,
+    Z,
+    L,
+}
+
+impl Field {
+    pub fn get(&self, op: &Op4D) -> f64 {
+        match self {
+            Field::T => op.t,
+            Field::X => op.x,
+            Field::Y => op.y,
+            Field::Z => op.z,
+            Field::L => op.l,
+        }
+    }
+
+    pub fn set(&self, op: &mut Op4D, value: f64) {
+        match self {
+            Field::T => op.t = value,
+            Field::X => op.x = value,
+            Field::Y => op.y = value,
+            Field::Z => op.z = value,
+            Field::L => op.l = value,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Curve {
+    Linear,
+    /// Raises the normalized value to a power, < 1.0 expands the low end.
+    Pow(f64),
+    Smoothstep,
+}
+
+impl Curve {
+    pub fn apply(&self, v: f64) -> f64 {
+        match self {
+            Curve::Linear => v,
+            Curve::Pow(exponent) => v.signum() * v.abs().powf(*exponent),
+            Curve::Smoothstep => {
+                let v = v.clamp(0.0, 1.0);
+                v * v * (3.0 - 2.0 * v)
+            }
+        }
+    }
+}
+
+/// Keeps ops with at least one of `names`, or drops them when `keep` is false.
+#[derive(Clone, Debug)]
+pub struct FilterNames {
+    pub names: Vec<String>,
+    pub keep: bool,
+}
+
+impl OpTransform for FilterNames {
+    fn apply(&self, ops: Vec<Op4D>) -> Vec<Op4D> {
+        ops.into_iter()
+            .filter(|op| op.names.iter().any(|name| self.names.contains(name)) == self.keep)
+            .collect()
+    }
+}
+
+/// Keeps only ops of the given event type.
+#[derive(Clone, Debug)]
+pub struct FilterEventType(pub EventType);
+
+impl OpTransform for FilterEventType {
+    fn apply(&self, ops: Vec<Op4D>) -> Vec<Op4D> {
+        ops.into_iter()
+            .filter(|op| discriminant(&op.event_type) == discriminant(&self.0))
+            .collect()
+    }
+}
+
+/// Keeps ops whose field lies in `min..=max`, e.g. dropping quiet ops with
+/// `FilterRange { field: Field::Z, min: 0.1, max: f64::INFINITY }`.
+#[derive(Clone, Debug)]
+pub struct FilterRange {
+    pub field: Field,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl OpTransform for FilterRange {
+    fn apply(&self, ops: Vec<Op4D>) -> Vec<Op4D> {
+        ops.into_iter()
+            .filter(|op| {
+                let value = self.field.get(op);
+                value >= self.min && value <= self.max
+            })
+            .collect()
+    }
+}
+
+/// Offsets a stream in time by seconds.
+#[derive(Clone, Debug)]
+pub struct TimeShift(pub f64);
+
+impl OpTransform for TimeShift {
+    fn apply(&self, ops: Vec<Op4D>) -> Vec<Op4D> {
+        ops.into_iter()
+            .map(|mut op| {
+                op.t += self.0;
+                op
+            })
+            .filter(|op| op.t >= 0.0)
+            .collect()
+    }
+}
+
+/// Scales time and note length.
+#[derive(Clone, Debug)]
+pub struct TimeScale(pub f64);
+
+impl OpTransform for TimeScale {
+    fn apply(&self, ops: Vec<Op4D>) -> Vec<Op4D> {
+        ops.into_iter()
+            .map(|mut op| {
+                op.t *= self.0;
+                op.l *= self.0;
+                op
+            })
+            .collect()
+    }
+}
+
+/// Snaps op times to the nearest multiple of the grid, in seconds.
+#[derive(Clone, Debug)]
+pub struct Quantize(pub f64);
+
+impl OpTransform for Quantize {
+    fn apply(&self, ops: Vec<Op4D>) -> Vec<Op4D> {
+        if self.0 <= 0.0 {
+            return ops;
+        }
+        ops.into_iter()
+            .map(|mut op| {
+                op.t = (op.t / self.0).round() * self.0;
+                op
+            })
+            .collect()
+    }
+}
+
+/// Maps a field from one range to another through a curve.
+#[derive(Clone, Debug)]
+pub struct Remap {
+    pub field: Field,
+    pub from: (f64, f64),
+    pub to: (f64, f64),
+    pub curve: Curve,
+}
+
+impl OpTransform for Remap {
+    fn apply(&self, ops: Vec<Op4D>) -> Vec<Op4D> {
+        let (from_min, from_max) = self.from;
+        let (to_min, to_max) = self.to;
+        ops.into_iter()
+            .map(|mut op| {
+                let normalized = if from_max == from_min {
+                    0.0
+                } else {
+                    (self.field.get(&op) - from_min) / (from_max - from_min)
+                };
+                let value = to_min + self.curve.apply(normalized) * (to_max - to_min);
+                self.field.set(&mut op, value);
+                op
+            })
+            .collect()
+    }
+}
+
+/// Keeps every nth op.
+#[derive(Clone, Debug)]
+pub struct Thin(pub usize);
+
+impl OpTransform for Thin {
+    fn apply(&self, ops: Vec<Op4D>) -> Vec<Op4D> {
+        ops.into_iter().step_by(self.0.max(1)).collect()
+    }
+}
+
+#[cfg(test)]
+fn op(t: f64, z: f64, name: &str) -> Op4D {
+    Op4D {
+        t,
+        voice: 0,
+        event: 0,
+        event_type: EventType::On,
+        x: 0.0,
+        y: 0.5,
+        z,
+        l: 1.0,
+        names: vec![name.to_string()],
+    }
+}
+
+#[test]
+fn test_filter_transforms() {
+    let ops = vec![op(0.0, 0.05, "a"), op(1.0, 0.5, "b"), op(2.0, 1.0, "a")];
+
+    let loud = FilterRange {
+        field: Field::Z,
+        min: 0.1,
+        max: f64::INFINITY,
+    }
+    .apply(ops.to_owned());
+    assert_eq!(
+        loud.iter().map(|op| op.t).collect::<Vec<_>>(),
+        vec![1.0, 2.0]
+    );
+
+    let only_a = FilterNames {
+        names: vec!["a".into()],
+        keep: true,
+    }
+    .apply(ops.to_owned());
+    assert_eq!(only_a.len(), 2);
+
+    let without_a = FilterNames {
+        names: vec!["a".into()],
+        keep: false,
+    }
+    .apply(ops.to_owned());
+    assert_eq!(without_a.len(), 1);
+    assert_eq!(without_a[0].names, vec!["b".to_string()]);
+
+    let on = FilterEventType(EventType::On).apply(ops);
+    assert_eq!(on.len(), 3);
+}
+
+#[test]
+fn test_time_transforms() {
+    let ops = vec![op(0.0, 1.0, "a"), op(0.26, 1.0, "a"), op(1.1, 1.0, "a")];
+
+    let shifted = TimeShift(-0.5).apply(ops.to_owned());
+    assert_eq!(shifted.len(), 1);
+    assert!((shifted[0].t - 0.6).abs() < 1e-12);
+
+    let scaled = TimeScale(2.0).apply(ops.to_owned());
+    assert_eq!(scaled[2].t, 2.2);
+    assert_eq!(scaled[2].l, 2.0);
+
+    // Streams are as long as their transformed ops.
+    let mut op_stream = super::OpStream {
+        ops: ops.to_owned(),
+        length: 2.1,
+        names: vec!["a".into()],
+    };
+    let transforms: Vec<Box<dyn OpTransform>> = vec![Box::new(TimeScale(2.0))];
+    op_stream.transform(&transforms);
+    assert!((op_stream.length - 4.2).abs() < 1e-6);
+
+    let quantized = Quantize(0.25).apply(ops);
+    assert_eq!(
+        quantized.iter().map(|op| op.t).collect::<Vec<_>>(),
+        vec![0.0, 0.25, 1.0]
+    );
+}
+
+#[test]
+fn test_remap_thin_and_pipeline() {
+    let ops = vec![op(2.0, 0.0, "a"), op(1.0, 0.5, "a"), op(0.0, 1.0, "a")];
+
+    let remapped = Remap {
+        field: Field::Z,
+        from: (0.0, 1.0),
+        to: (10.0, 20.0),
+        curve: Curve::Pow(2.0),
+    }
+    .apply(ops.to_owned());
+    assert_eq!(
+        remapped.iter().map(|op| op.z).collect::<Vec<_>>(),
+        vec![10.0, 12.5, 20.0]
+    );
+
+    let thinned = Thin(2).apply(ops.to_owned());
+    assert_eq!(thinned.len(), 2);
+
+    let transforms: Vec<Box<dyn OpTransform>> = vec![Box::new(TimeShift(1.0)), Box::new(Thin(1))];
+    let result = apply_transforms(ops, &transforms);
+    assert_eq!(
+        result.iter().map(|op| op.t).collect::<Vec<_>>(),
+        vec![1.0, 2.0, 3.0]
+    );
+}
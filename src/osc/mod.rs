@@ -0,0 +1,287 @@
+use kintaro_egui_lib::{InstanceMulField, UiState};
+use rosc::{decoder, encoder, OscMessage, OscPacket, OscType};
+use std::net::{SocketAddr, UdpSocket};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use weresocool::error::Error;
+
+use crate::op_stream::Op4D;
+
+/// The prefix of every address kintaro sends and understands.
+pub const PREFIX: &str = "/kintaro";
+
+/// Where OSC is received and sent. Both are off by default, `--osc` listens
+/// on `OscConfig::LISTEN` and `--osc-send <addr>` sends to `addr`.
+#[derive(Clone, Debug, Default)]
+pub struct OscConfig {
+    pub listen: Option<String>,
+    pub send: Option<String>,
+}
+
+impl OscConfig {
+    pub const LISTEN: &'static str = "127.0.0.1:9000";
+}
+
+/// Applies OSC messages to the GUI state on a background thread, e.g.
+///
+/// ```text
+/// /kintaro/volume 0.5
+/// /kintaro/instance_mul/size 40.0
+/// /kintaro/camera 2              or a preset name
+/// /kintaro/play 1
+/// /kintaro/shake 0.5             also fov_pulse, dolly and orbit_speed
+/// /kintaro/stream/0/mute 1       a stream index or name, also solo and hide
+/// ```
+pub struct OscServer {
+    pub addr: SocketAddr,
+}
+
+impl OscServer {
+    pub fn listen(addr: &str, state: Arc<Mutex<UiState>>) -> Result<Self, Error> {
+        let socket = UdpSocket::bind(addr)
+            .map_err(|e| Error::with_msg(format!("Unable to listen for osc on {}: {}", addr, e)))?;
+        let addr = socket.local_addr()?;
+        println!("osc listening on {}", addr);
+        std::thread::spawn(move || {
+            let mut buf = [0u8; rosc::decoder::MTU];
+            loop {
+                let size = match socket.recv_from(&mut buf) {
+                    Ok((size, _)) => size,
+                    Err(e) if is_transient(&e) => {
+                        std::thread::sleep(Duration::from_millis(100));
+                        continue;
+                    }
+                    Err(e) => {
+                        println!("osc stopped listening: {}", e);
+                        break;
+                    }
+                };
+                match decoder::decode_udp(&buf[..size]) {
+                    Ok((_, packet)) => {
+                        let mut s = state.lock().unwrap();
+                        if let Err(e) = apply_packet(&packet, &mut s) {
+                            println!("{:?}", e);
+                        }
+                    }
+                    Err(e) => println!("unable to decode osc packet: {:?}", e),
+                }
+            }
+        });
+        Ok(Self { addr })
+    }
+}
+
+fn apply_packet(packet: &OscPacket, state: &mut UiState) -> Result<(), Error> {
+    match packet {
+        OscPacket::Message(message) => apply_message(message, state),
+        OscPacket::Bundle(bundle) => bundle
+            .content
+            .iter()
+            .try_for_each(|packet| apply_packet(packet, state)),
+    }
+}
+
+/// Errors worth retrying, anything else means the socket is unusable.
+fn is_transient(e: &std::io::Error) -> bool {
+    use std::io::ErrorKind::*;
+    matches!(
+        e.kind(),
+        Interrupted | WouldBlock | TimedOut | ConnectionReset | ConnectionRefused
+    )
+}
+
+/// Sets the `UiState` field the message addresses. Values are in the GUI's
+/// units, unlike midi's 0..1, and buttons are set rather than toggled.
+pub fn apply_message(message: &OscMessage, state: &mut UiState) -> Result<(), Error> {
+    let unknown = || Error::with_msg(format!("unknown osc address {}", message.addr));
+    let path: Vec<&str> = message
+        .addr
+        .strip_prefix(PREFIX)
+        .and_then(|path| path.strip_prefix('/'))
+        .ok_or_else(unknown)?
+        .split('/')
+        .collect();
+    let arg = message
+        .args
+        .first()
+        .ok_or_else(|| Error::with_msg(format!("osc message {} has no argument", message.addr)))?;
+    let value = || {
+        number(arg).ok_or_else(|| {
+            Error::with_msg(format!(
+                "osc message {} needs a number, got {:?}",
+                message.addr, arg
+            ))
+        })
+    };
+
+    match path.as_slice() {
+        ["volume"] => state.volume = value()?.clamp(0.0, 1.0),
+        ["instance_mul", field] => InstanceMulField::from_name(field)
+            .ok_or_else(unknown)?
+            .set(&mut state.instance_mul, value()?),
+        ["shake"] => state.reactivity.shake = value()?,
+        ["fov_pulse"] => state.reactivity.fov_pulse = value()?,
+        ["dolly"] => state.reactivity.dolly = value()?,
+        ["orbit_speed"] => state.orbit_speed = value()?,
+        ["play"] => state.play = value()? >= 0.5,
+        ["camera"] => {
+            let idx = match arg {
+                OscType::String(name) => state.cameras.iter().position(|camera| camera == name),
+                _ => Some(value()? as usize).filter(|idx| *idx < state.cameras.len()),
+            };
+            state.camera_index =
+                idx.ok_or_else(|| Error::with_msg(format!("no camera preset {:?}", arg)))?;
+        }
+        ["stream", stream, toggle] => {
+            let on = value()? >= 0.5;
+            let stream = match stream.parse::<usize>() {
+                Ok(idx) => state.streams.get_mut(idx),
+                Err(_) => state.streams.iter_mut().find(|s| s.name == *stream),
+            }
+            .ok_or_else(|| Error::with_msg(format!("no stream {}", stream)))?;
+            match *toggle {
+                "mute" => stream.mute = on,
+                "solo" => stream.solo = on,
+                "hide" => stream.hidden = on,
+                _ => return Err(unknown()),
+            }
+        }
+        _ => return Err(unknown()),
+    }
+    Ok(())
+}
+
+fn number(arg: &OscType) -> Option<f32> {
+    match *arg {
+        OscType::Float(value) => Some(value),
+        OscType::Double(value) => Some(value as f32),
+        OscType::Int(value) => Some(value as f32),
+        OscType::Long(value) => Some(value as f32),
+        OscType::Bool(value) => Some(if value { 1.0 } else { 0.0 }),
+        _ => None,
+    }
+}
+
+/// Sends the playhead and the ops of each stream as they are played:
+///
+/// ```text
+/// /kintaro/playhead <seconds>
+/// /kintaro/stream/<index>/on <name> <voice> <event> <x> <y> <z> <length>
+/// /kintaro/stream/<index>/off <name> <voice> <event>
+/// ```
+///
+/// Sending never fails the render, a missing receiver is ignored.
+pub struct OscOutput {
+    socket: UdpSocket,
+    target: SocketAddr,
+}
+
+impl OscOutput {
+    pub fn connect(target: &str) -> Result<Self, Error> {
+        let target: SocketAddr = target
+            .parse()
+            .map_err(|e| Error::with_msg(format!("Invalid osc address {}: {}", target, e)))?;
+        let bind: SocketAddr = if target.is_ipv4() {
+            "0.0.0.0:0".parse().unwrap()
+        } else {
+            "[::]:0".parse().unwrap()
+        };
+        let socket = UdpSocket::bind(bind)?;
+        println!("osc sending to {}", target);
+        Ok(Self { socket, target })
+    }
+
+    pub fn send(&self, addr: String, args: Vec<OscType>) {
+        let packet = OscPacket::Message(OscMessage { addr, args });
+        if let Ok(bytes) = encoder::encode(&packet) {
+            self.socket.send_to(&bytes, self.target).ok();
+        }
+    }
+
+    pub fn playhead(&self, t: f32) {
+        self.send(format!("{}/playhead", PREFIX), vec![OscType::Float(t)]);
+    }
+
+    pub fn stream_events(&self, idx: usize, name: &str, ons: &[Op4D], offs: &[Op4D]) {
+        ons.iter().for_each(|op| {
+            self.send(
+                format!("{}/stream/{}/on", PREFIX, idx),
+                vec![
+                    OscType::String(name.to_string()),
+                    OscType::Int(op.voice as i32),
+                    OscType::Int(op.event as i32),
+                    OscType::Float(op.x as f32),
+                    OscType::Float(op.y as f32),
+                    OscType::Float(op.z as f32),
+                    OscType::Float(op.l as f32),
+                ],
+            )
+        });
+        offs.iter().for_each(|op| {
+            self.send(
+                format!("{}/stream/{}/off", PREFIX, idx),
+                vec![
+                    OscType::String(name.to_string()),
+                    OscType::Int(op.voice as i32),
+                    OscType::Int(op.event as i32),
+                ],
+            )
+        });
+    }
+}
+
+#[test]
+fn test_osc_from_localhost_client() {
+    use std::time::Instant;
+
+    let state = Arc::new(Mutex::new(UiState {
+        cameras: vec!["front".into(), "side".into(), "top".into()],
+        streams: vec![kintaro_egui_lib::StreamState::init("a".into(), None)],
+        ..UiState::default()
+    }));
+    let server = OscServer::listen("127.0.0.1:0", state.clone()).unwrap();
+    let client = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let send = |addr: &str, arg: OscType| {
+        let packet = OscPacket::Message(OscMessage {
+            addr: addr.into(),
+            args: vec![arg],
+        });
+        client
+            .send_to(&encoder::encode(&packet).unwrap(), server.addr)
+            .unwrap();
+    };
+    send("/kintaro/instance_mul/size", OscType::Float(40.0));
+    send("/kintaro/camera", OscType::Int(2));
+    send("/kintaro/play", OscType::Int(1));
+    send("/kintaro/nothing", OscType::Int(1));
+    send("/kintaroXYZ/volume", OscType::Float(0.9));
+    send("/kintaro/stream/a/mute", OscType::Bool(true));
+
+    let start = Instant::now();
+    while !state.lock().unwrap().streams[0].mute {
+        assert!(start.elapsed() < Duration::from_secs(5), "osc timed out");
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    let s = state.lock().unwrap();
+    assert_eq!(s.instance_mul.size, 40.0);
+    assert_eq!(s.camera_index, 2);
+    assert!(s.play);
+    assert_ne!(s.volume, 0.9);
+
+    // Events are sent to a local receiver.
+    let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+    receiver
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    let output = OscOutput::connect(&receiver.local_addr().unwrap().to_string()).unwrap();
+    output.playhead(1.5);
+    let mut buf = [0u8; rosc::decoder::MTU];
+    let (size, _) = receiver.recv_from(&mut buf).unwrap();
+    match decoder::decode_udp(&buf[..size]).unwrap().1 {
+        OscPacket::Message(message) => {
+            assert_eq!(message.addr, "/kintaro/playhead");
+            assert_eq!(message.args, vec![OscType::Float(1.5)]);
+        }
+        packet => panic!("unexpected packet {:?}", packet),
+    }
+}
@@ -0,0 +1,215 @@
+//! Golden image tests. Frames of a small deterministic piece are rendered
+//! offscreen and compared against the reference pngs in `./golden`.
+//!
+//! A missing reference fails the test. Set `KINTARO_BLESS=1` to write
+//! missing references, or replace them after an intended change. On failure
+//! the actual frame and a diff image are written to `./golden/failures`.
+//!
+//! The frame test is ignored by default, it needs a wgpu adapter, ideally a
+//! software one so that references match across machines, and the blessed
+//! references. Run it with `cargo test -- --ignored golden`, it passes
+//! without checking anything when there is no adapter.
+
+use image::{Rgba, RgbaImage};
+use std::path::Path;
+
+use super::PrintState;
+use crate::audio::analysis::AudioFeatures;
+use crate::config::Config;
+use crate::op_stream::{EventType, Op4D, OpStream};
+use crate::shared::{request_device, DevicePolicy, Fallback};
+
+const GOLDEN_DIR: &str = "./golden";
+
+#[derive(Copy, Clone, Debug)]
+pub struct Tolerance {
+    /// Largest difference of any channel for a pixel to still match.
+    pub channel: u8,
+    /// Fraction of pixels that may differ, to absorb rasterization
+    /// differences between adapters.
+    pub pixels: f64,
+}
+
+impl Default for Tolerance {
+    fn default() -> Self {
+        Self {
+            channel: 8,
+            pixels: 0.005,
+        }
+    }
+}
+
+/// Compares two frames, returning the fraction of differing pixels and a
+/// diff image with differing pixels in red over a dimmed `expected`.
+pub fn diff(actual: &RgbaImage, expected: &RgbaImage, tolerance: u8) -> (f64, RgbaImage) {
+    assert_eq!(actual.dimensions(), expected.dimensions());
+    let mut n_differing = 0;
+    let diff = RgbaImage::from_fn(expected.width(), expected.height(), |x, y| {
+        let a = actual.get_pixel(x, y);
+        let e = expected.get_pixel(x, y);
+        let differs =
+            a.0.iter()
+                .zip(e.0.iter())
+                .any(|(a, e)| (*a as i16 - *e as i16).unsigned_abs() as u8 > tolerance);
+        if differs {
+            n_differing += 1;
+            Rgba([255, 0, 0, 255])
+        } else {
+            let luma = (e[0] as u16 + e[1] as u16 + e[2] as u16) / 3 / 4;
+            Rgba([luma as u8, luma as u8, luma as u8, 255])
+        }
+    });
+    let n_pixels = (expected.width() * expected.height()).max(1) as f64;
+
+    (n_differing as f64 / n_pixels, diff)
+}
+
+pub fn assert_golden(name: &str, actual: &RgbaImage, tolerance: Tolerance) {
+    let reference = Path::new(GOLDEN_DIR).join(format!("{}.png", name));
+    if std::env::var_os("KINTARO_BLESS").is_some() {
+        std::fs::create_dir_all(GOLDEN_DIR).unwrap();
+        actual.save(&reference).unwrap();
+        println!("wrote golden image {}", reference.display());
+        return;
+    }
+    if !reference.is_file() {
+        panic!(
+            "{}: no reference {}, run with KINTARO_BLESS=1 to write it",
+            name,
+            reference.display()
+        );
+    }
+
+    let expected = image::open(&reference).unwrap().to_rgba8();
+    let failures = Path::new(GOLDEN_DIR).join("failures");
+    if expected.dimensions() != actual.dimensions() {
+        std::fs::create_dir_all(&failures).unwrap();
+        actual
+            .save(failures.join(format!("{}.actual.png", name)))
+            .unwrap();
+        panic!(
+            "{}: size {:?} doesn't match the reference {:?}",
+            name,
+            actual.dimensions(),
+            expected.dimensions()
+        );
+    }
+
+    let (differing, diff_img) = diff(actual, &expected, tolerance.channel);
+    if differing > tolerance.pixels {
+        std::fs::create_dir_all(&failures).unwrap();
+        actual
+            .save(failures.join(format!("{}.actual.png", name)))
+            .unwrap();
+        diff_img
+            .save(failures.join(format!("{}.diff.png", name)))
+            .unwrap();
+        panic!(
+            "{}: {:.2}% of pixels differ from {}, see {}",
+            name,
+            differing * 100.0,
+            reference.display(),
+            failures.display()
+        );
+    }
+}
+
+/// A device on the software fallback adapter if there is one, otherwise on
+/// any adapter. `None` when the machine has no adapter at all.
+async fn test_device() -> Option<(wgpu::Device, wgpu::Queue)> {
+    let policy = DevicePolicy {
+        fallback: Fallback::Only,
+        ..DevicePolicy::default()
+    };
+    let instance = wgpu::Instance::new(policy.backends);
+    let device = match request_device(&instance, &policy, None).await {
+        Ok(device) => Some(device),
+        Err(_) => {
+            let policy = DevicePolicy::default();
+            request_device(&instance, &policy, None).await.ok()
+        }
+    };
+    device.map(|(_adapter, device, queue)| (device, queue))
+}
+
+fn op(t: f64, voice: usize, x: f64, y: f64, name: &str) -> Op4D {
+    Op4D {
+        t,
+        voice,
+        event: voice,
+        event_type: EventType::On,
+        x,
+        y,
+        z: 0.8,
+        l: 0.1,
+        names: vec![name.to_string()],
+    }
+}
+
+/// Independent of `./save`, so saved state can't change the frames.
+fn test_config() -> Config {
+    let mut config = Config::unsaved();
+    config.window_size = (256, 144);
+    config.seed = 1;
+    config
+}
+
+fn test_op_streams() -> Vec<OpStream> {
+    vec![
+        OpStream {
+            names: vec!["a".into()],
+            ops: vec![
+                op(0.0, 0, -0.5, 0.2, "a"),
+                op(0.05, 1, 0.0, 0.5, "a"),
+                op(0.1, 2, 0.5, 0.8, "a"),
+            ],
+            length: 0.5,
+        },
+        OpStream {
+            names: vec!["b".into()],
+            ops: vec![op(0.0, 0, 0.3, -0.4, "b"), op(0.15, 1, -0.3, -0.1, "b")],
+            length: 0.5,
+        },
+    ]
+}
+
+#[test]
+fn test_diff_tolerance() {
+    let expected = RgbaImage::from_pixel(4, 4, Rgba([100, 100, 100, 255]));
+    let mut actual = expected.clone();
+    actual.put_pixel(0, 0, Rgba([104, 100, 100, 255]));
+    actual.put_pixel(1, 0, Rgba([200, 100, 100, 255]));
+
+    let (differing, diff_img) = diff(&actual, &expected, 8);
+    assert_eq!(differing, 1.0 / 16.0);
+    assert_eq!(diff_img.get_pixel(1, 0), &Rgba([255, 0, 0, 255]));
+    assert_ne!(diff_img.get_pixel(0, 0), &Rgba([255, 0, 0, 255]));
+}
+
+#[test]
+#[ignore = "needs a wgpu adapter and the references in ./golden"]
+fn test_golden_frames() {
+    let (device, queue) = match pollster::block_on(test_device()) {
+        Some(device) => device,
+        None => {
+            println!("no wgpu adapter available, skipping golden image test");
+            return;
+        }
+    };
+    let mut config = test_config();
+    let mut state = PrintState::with_device(
+        device,
+        queue,
+        &mut config,
+        test_op_streams(),
+        AudioFeatures::default(),
+    )
+    .expect("unable to set up print state");
+
+    for frame in 1..=12 {
+        let img = pollster::block_on(state.render_frame());
+        if frame % 4 == 0 {
+            assert_golden(&format!("frame_{:03}", frame), &img, Tolerance::default());
+        }
+    }
+}
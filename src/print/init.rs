@@ -1,10 +1,12 @@
 use weresocool::error::Error;
-use weresocool::generation::parsed_to_render::AudioVisual;
 
 use super::PrintState;
+use crate::audio::analysis::AudioFeatures;
 use crate::composition::Composition;
 use crate::op_stream::renderpasses::make_renderpasses;
+use crate::op_stream::OpStream;
 use crate::shader::make_shader;
+use crate::shared::request_device;
 use crate::{
     canvas::Canvas,
     clock::{Clock, PrintClock},
@@ -12,24 +14,30 @@ use crate::{
 };
 
 impl PrintState {
-    pub async fn init(config: &mut Config, av: &AudioVisual) -> Result<PrintState, Error> {
+    pub async fn init(
+        config: &mut Config,
+        op_streams: Vec<OpStream>,
+        features: AudioFeatures,
+    ) -> Result<PrintState, Error> {
         let size = config.window_size;
         dbg!(&config.window_size);
         println!("{}/{}", size.0, size.1);
-        let instance = wgpu::Instance::new(wgpu::Backends::PRIMARY);
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::default(),
-                compatible_surface: None,
-                force_fallback_adapter: false,
-            })
-            .await
-            .unwrap();
-        let (device, queue) = adapter
-            .request_device(&Default::default(), None)
-            .await
-            .unwrap();
+        let instance = wgpu::Instance::new(config.device.backends);
+        let (_adapter, device, queue) = request_device(&instance, &config.device, None).await?;
 
+        PrintState::with_device(device, queue, config, op_streams, features)
+    }
+
+    /// Builds the offscreen render state on an existing device, e.g. one on a
+    /// fallback adapter in tests.
+    pub fn with_device(
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+        config: &mut Config,
+        op_streams: Vec<OpStream>,
+        features: AudioFeatures,
+    ) -> Result<PrintState, Error> {
+        let size = config.window_size;
         let texture_desc = wgpu::TextureDescriptor {
             size: wgpu::Extent3d {
                 width: size.0,
@@ -51,8 +59,6 @@ impl PrintState {
 
         let toy = crate::toy::setup_toy(&device, toy_shader, size, texture_desc.format);
 
-        let op_streams = crate::op_stream::OpStream::from_vec_op4d(&av);
-
         let renderpasses = make_renderpasses(
             &device,
             op_streams,
@@ -60,26 +66,29 @@ impl PrintState {
             config,
             texture_desc.format,
         );
-        todo!();
 
-        // Ok(PrintState {
-        // device,
-        // queue,
-        // size,
-        // clock: PrintClock::init(&config),
-        // count: 0,
+        Ok(PrintState {
+            clock: PrintClock::init(&config),
+            count: 0,
 
-        // composition: Composition {
-        // config: config.clone(),
-        // camera: crate::camera::Camera::new(&config.cameras[0], size, &config, 0),
-        // renderpasses,
-        // toy: Some(toy),
-        // canvas: Canvas::init(size),
-        // },
+            composition: Composition {
+                config: config.clone(),
+                camera: crate::camera::Camera::new(config.cameras.camera(0), size, &config, 0),
+                renderpasses,
+                toy: Some(toy),
+                canvas: Canvas::init(size),
+                image_renderer: None,
+                follow_path: !config.camera_path.is_empty(),
+                features,
+                osc: None,
+            },
 
-        // texture,
-        // texture_view,
-        // time_elapsed: std::time::Duration::from_millis(0),
-        // })
+            device,
+            queue,
+            size,
+            texture,
+            texture_view,
+            time_elapsed: std::time::Duration::from_millis(0),
+        })
     }
 }
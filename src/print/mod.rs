@@ -1,6 +1,8 @@
+#[cfg(test)]
+mod golden;
 mod init;
 mod render;
-mod write;
+pub mod write;
 use crate::clock::PrintClock;
 use crate::composition::Composition;
 
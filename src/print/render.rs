@@ -1,12 +1,20 @@
 use crate::clock::Clock;
+use image::RgbaImage;
 
 use super::{
-    write::{copy_texture_to_buffer, write_img},
+    write::{copy_texture_to_buffer, read_img, write_img},
     PrintState,
 };
 
 impl PrintState {
     pub async fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
+        let img = self.render_frame().await;
+        write_img(&img, self.clock.frame_count);
+        Ok(())
+    }
+
+    /// Advances the clock by one frame and returns the rendered frame.
+    pub async fn render_frame(&mut self) -> RgbaImage {
         self.clock.update();
 
         let mut encoder = self
@@ -30,13 +38,6 @@ impl PrintState {
 
         self.queue.submit(Some(encoder.finish()));
 
-        write_img(
-            output_buffer,
-            self.clock.frame_count,
-            self.size,
-            &self.device,
-        )
-        .await;
-        Ok(())
+        read_img(output_buffer, self.size, &self.device).await
     }
 }
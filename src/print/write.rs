@@ -1,13 +1,13 @@
-use image::{ImageBuffer, Rgba};
+use image::{ImageBuffer, Rgba, RgbaImage};
 const U32_SIZE: u32 = std::mem::size_of::<u32>() as u32;
 
-pub async fn write_img(
+/// Reads a buffer filled by `copy_texture_to_buffer` back into an image.
+pub async fn read_img(
     output_buffer: wgpu::Buffer,
-    frame: u32,
     size: (u32, u32),
     device: &wgpu::Device,
-) {
-    {
+) -> RgbaImage {
+    let img = {
         let buffer_slice = output_buffer.slice(..);
 
         // NOTE: We have to create the mapping THEN device.poll() before await
@@ -16,16 +16,25 @@ pub async fn write_img(
         device.poll(wgpu::Maintain::Wait);
         mapping.await.unwrap();
 
-        let data = buffer_slice.get_mapped_range();
+        // Rows are padded to the copy alignment, keep only the pixels.
+        let row = (U32_SIZE * size.0) as usize;
+        let data = buffer_slice
+            .get_mapped_range()
+            .chunks(padded_bytes_per_row(size.0) as usize)
+            .flat_map(|padded| padded[..row].to_vec())
+            .collect();
+        ImageBuffer::<Rgba<u8>, _>::from_raw(size.0, size.1, data).unwrap()
+    };
+    output_buffer.unmap();
+    img
+}
 
-        let buffer = ImageBuffer::<Rgba<u8>, _>::from_raw(size.0, size.1, data).unwrap();
-        let filename = format!("out/{:07}.png", frame);
-        if frame % 100 == 0 {
-            dbg!(&filename);
-        }
-        buffer.save(filename).unwrap();
+pub fn write_img(img: &RgbaImage, frame: u32) {
+    let filename = format!("out/{:07}.png", frame);
+    if frame % 100 == 0 {
+        dbg!(&filename);
     }
-    output_buffer.unmap();
+    img.save(filename).unwrap();
 }
 
 pub fn copy_texture_to_buffer(
@@ -47,7 +56,7 @@ pub fn copy_texture_to_buffer(
             buffer: &output_buffer,
             layout: wgpu::ImageDataLayout {
                 offset: 0,
-                bytes_per_row: std::num::NonZeroU32::new(U32_SIZE * size.0),
+                bytes_per_row: std::num::NonZeroU32::new(padded_bytes_per_row(size.0)),
                 rows_per_image: std::num::NonZeroU32::new(size.1),
             },
         },
@@ -61,7 +70,7 @@ pub fn copy_texture_to_buffer(
 }
 
 fn make_output_buffer(device: &wgpu::Device, size: (u32, u32)) -> wgpu::Buffer {
-    let output_buffer_size = (U32_SIZE * size.0 * size.1) as wgpu::BufferAddress;
+    let output_buffer_size = (padded_bytes_per_row(size.0) * size.1) as wgpu::BufferAddress;
     let output_buffer_desc = wgpu::BufferDescriptor {
         size: output_buffer_size,
         usage: wgpu::BufferUsages::COPY_DST
@@ -72,3 +81,10 @@ fn make_output_buffer(device: &wgpu::Device, size: (u32, u32)) -> wgpu::Buffer {
     };
     device.create_buffer(&output_buffer_desc)
 }
+
+/// Texture copies need rows aligned to `COPY_BYTES_PER_ROW_ALIGNMENT`, so
+/// widths that aren't a multiple of 64 pixels are padded.
+fn padded_bytes_per_row(width: u32) -> u32 {
+    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    (U32_SIZE * width + align - 1) / align * align
+}
@@ -0,0 +1,145 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use weresocool::error::Error;
+use winit::event::VirtualKeyCode;
+
+use crate::camera::Movement;
+
+/// What a key does in realtime mode.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum KeyAction {
+    /// Held down to move the camera.
+    Move(Movement),
+    Faster,
+    Slower,
+    /// Moves to the camera preset at the index.
+    Camera(usize),
+    PlayPause,
+    /// Moves the playhead by seconds, negative to go back.
+    Seek(f32),
+    Screenshot,
+    Save,
+    Quit,
+    PreviousStream,
+    NextStream,
+    /// Mute, solo and hide toggle the selected stream.
+    Mute,
+    Solo,
+    Hide,
+}
+
+/// Keys by their winit names, e.g. `"W"`, `"Key1"` or `"Space"`. A keys
+/// file replaces the defaults entirely, e.g.
+///
+/// ```json
+/// { "keys": { "W": { "Move": "Forward" }, "Space": "PlayPause", "Period": { "Seek": 10.0 } } }
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct KeyBindings {
+    pub keys: BTreeMap<VirtualKeyCode, KeyAction>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        use KeyAction::*;
+        use VirtualKeyCode as Key;
+
+        let mut keys = BTreeMap::new();
+        let movements = [
+            (Key::W, Movement::Forward),
+            (Key::Up, Movement::Forward),
+            (Key::S, Movement::Backward),
+            (Key::Down, Movement::Backward),
+            (Key::A, Movement::Left),
+            (Key::Left, Movement::Left),
+            (Key::D, Movement::Right),
+            (Key::Right, Movement::Right),
+            (Key::K, Movement::Up),
+            (Key::J, Movement::Down),
+            (Key::H, Movement::TurnLeft),
+            (Key::L, Movement::TurnRight),
+        ];
+        movements.iter().for_each(|(key, movement)| {
+            keys.insert(*key, Move(*movement));
+        });
+        let cameras = [
+            Key::Key1,
+            Key::Key2,
+            Key::Key3,
+            Key::Key4,
+            Key::Key5,
+            Key::Key6,
+            Key::Key7,
+            Key::Key8,
+            Key::Key9,
+        ];
+        cameras.iter().enumerate().for_each(|(idx, key)| {
+            keys.insert(*key, Camera(idx));
+        });
+        keys.extend([
+            (Key::Equals, Faster),
+            (Key::Minus, Slower),
+            (Key::Space, PlayPause),
+            (Key::Period, Seek(5.0)),
+            (Key::Comma, Seek(-5.0)),
+            (Key::P, Screenshot),
+            (Key::F5, Save),
+            (Key::Escape, Quit),
+            (Key::LBracket, PreviousStream),
+            (Key::RBracket, NextStream),
+            (Key::M, Mute),
+            (Key::O, Solo),
+            (Key::V, Hide),
+        ]);
+        Self { keys }
+    }
+}
+
+impl KeyBindings {
+    pub const PATH: &'static str = "./save/keys.json";
+
+    /// Loads the bindings from `path`, keeping `self` when there is no file.
+    pub fn load(self, path: &Path) -> Result<Self, Error> {
+        if !path.is_file() {
+            return Ok(self);
+        }
+        println!("Loading key bindings from {}", path.display());
+        let data = std::fs::read_to_string(path)?;
+        serde_json::from_str::<KeyBindings>(&data)
+            .map_err(|e| Error::with_msg(format!("Unable to parse {}: {}", path.display(), e)))
+    }
+
+    pub fn action(&self, key: VirtualKeyCode) -> Option<KeyAction> {
+        self.keys.get(&key).copied()
+    }
+}
+
+#[test]
+fn test_key_bindings_load_by_key_name() {
+    let json = r#"{ "keys": { "W": { "Move": "Up" }, "Key2": { "Camera": 1 }, "Q": "Quit", "Period": { "Seek": 10.0 } } }"#;
+    let bindings: KeyBindings = serde_json::from_str(json).unwrap();
+    assert_eq!(
+        bindings.action(VirtualKeyCode::W),
+        Some(KeyAction::Move(Movement::Up))
+    );
+    assert_eq!(
+        bindings.action(VirtualKeyCode::Key2),
+        Some(KeyAction::Camera(1))
+    );
+    assert_eq!(bindings.action(VirtualKeyCode::Q), Some(KeyAction::Quit));
+    assert_eq!(
+        bindings.action(VirtualKeyCode::Period),
+        Some(KeyAction::Seek(10.0))
+    );
+    assert_eq!(bindings.action(VirtualKeyCode::Escape), None);
+
+    let defaults = KeyBindings::default();
+    assert_eq!(
+        defaults.action(VirtualKeyCode::Key1),
+        Some(KeyAction::Camera(0))
+    );
+    let roundtrip: KeyBindings =
+        serde_json::from_str(&serde_json::to_string(&defaults).unwrap()).unwrap();
+    assert_eq!(roundtrip, defaults);
+}
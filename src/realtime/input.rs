@@ -1,37 +1,97 @@
+use crate::realtime::bindings::KeyAction;
 use crate::realtime::RealTimeState;
 use winit::event::*;
 
 impl RealTimeState {
-    pub fn keyboard_input(&mut self, event: &WindowEvent) {
-        match event {
-            WindowEvent::KeyboardInput { input, .. } => match input {
-                KeyboardInput {
-                    state,
-                    virtual_keycode: Some(key),
-                    ..
-                } => {
-                    self.composition
-                        .camera
-                        .controller
-                        .process_keyboard(*key, *state);
+    /// Runs the action bound to the key. Returns whether it asked to quit.
+    pub fn keyboard_input(&mut self, event: &WindowEvent) -> bool {
+        let (key, state) = match event {
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        state,
+                        virtual_keycode: Some(key),
+                        ..
+                    },
+                ..
+            } => (*key, *state),
+            _ => return false,
+        };
+        let action = match self.composition.config.key_bindings.action(key) {
+            Some(action) => action,
+            None => return false,
+        };
+
+        // Releases always stop movements, so keys can't stick while the GUI
+        // takes the keyboard.
+        if let KeyAction::Move(movement) = action {
+            if state == ElementState::Released
+                || !self.gui.platform.context().wants_keyboard_input()
+            {
+                self.composition
+                    .camera
+                    .controller
+                    .process_movement(movement, state);
+            }
+            return false;
+        }
+        if state != ElementState::Pressed || self.gui.platform.context().wants_keyboard_input() {
+            return false;
+        }
+        self.key_action(action)
+    }
+
+    fn key_action(&mut self, action: KeyAction) -> bool {
+        match action {
+            KeyAction::Move(_) => {}
+            KeyAction::Faster => self.composition.camera.controller.scale_speed(1.5),
+            KeyAction::Slower => self.composition.camera.controller.scale_speed(1.0 / 1.5),
+            KeyAction::Seek(seconds) => {
+                if let Err(e) = self.seek(seconds) {
+                    self.gui.state.lock().unwrap().status = Some(format!("error seeking: {:?}", e));
+                }
+            }
+            KeyAction::Quit => return true,
+            _ => self.ui_key_action(action),
+        }
+        false
+    }
+
+    /// Actions that go through the GUI state, handled like its buttons.
+    fn ui_key_action(&mut self, action: KeyAction) {
+        let mut s = self.gui.state.lock().unwrap();
+        match action {
+            KeyAction::Camera(idx) => {
+                if idx < s.cameras.len() {
+                    s.camera_index = idx
+                }
+            }
+            KeyAction::PlayPause => s.play = !s.play,
+            KeyAction::Screenshot => s.screenshot = true,
+            KeyAction::Save => s.save = true,
+            KeyAction::PreviousStream => s.select_stream(-1),
+            KeyAction::NextStream => s.select_stream(1),
+            KeyAction::Mute => {
+                if let Some(stream) = s.selected_stream_mut() {
+                    stream.mute = !stream.mute
                 }
-                _ => {}
-            },
+            }
+            KeyAction::Solo => {
+                if let Some(stream) = s.selected_stream_mut() {
+                    stream.solo = !stream.solo
+                }
+            }
+            KeyAction::Hide => {
+                if let Some(stream) = s.selected_stream_mut() {
+                    stream.hidden = !stream.hidden
+                }
+            }
             _ => {}
         }
     }
 
     pub fn input(&mut self, event: &DeviceEvent) -> bool {
         match event {
-            DeviceEvent::Key(KeyboardInput {
-                virtual_keycode: Some(key),
-                state,
-                ..
-            }) => self
-                .composition
-                .camera
-                .controller
-                .process_keyboard(*key, *state),
             DeviceEvent::MouseWheel { delta, .. } => {
                 self.composition.camera.controller.process_scroll(&*delta);
                 true
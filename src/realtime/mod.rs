@@ -1,15 +1,24 @@
+pub mod bindings;
 mod input;
+pub mod reload;
 pub mod render;
 mod resize;
+pub mod screenshot;
 pub mod setup;
 
+use crate::application::AudioVisualStreams;
+use crate::audio::Tracks;
 use crate::canvas::Canvas;
 use crate::image_renderer::ImageRenderer;
+use crate::midi::MidiInput;
+use crate::save::ConfigState;
 use crate::shader::make_shader;
+use crate::shared::RenderPassInput;
 use crate::{composition::Composition, op_stream::renderpasses::make_renderpasses};
+use reload::Reloader;
 use setup::Setup;
+use std::time::Duration;
 use weresocool::error::Error;
-use weresocool::generation::parsed_to_render::AudioVisual;
 
 use crate::{
     clock::{Clock, RenderClock},
@@ -17,6 +26,7 @@ use crate::{
     realtime::render::ExampleRepaintSignal,
 };
 use futures::executor::block_on;
+use kintaro_egui_lib::{StemState, StreamState, UiState};
 use winit::window::Window;
 
 use self::setup::Gui;
@@ -35,7 +45,14 @@ pub struct RealTimeState {
     pub mouse_pressed: bool,
     pub gui: Gui,
     pub repaint_signal: std::sync::Arc<ExampleRepaintSignal>,
-    pub audio_stream_handle: rodio::Sink,
+    pub audio: Tracks,
+    pub reloader: Option<Reloader>,
+    /// The piece being played, kept to seek in it.
+    pub av: AudioVisualStreams,
+    pub midi: Option<MidiInput>,
+    /// The GUI's selected stream as of the last frame. LookAt only follows the
+    /// selection when it changes, so presets and cues keep their stream.
+    pub selected_stream: usize,
 }
 
 impl RealTimeState {
@@ -43,8 +60,8 @@ impl RealTimeState {
         window: &Window,
         config: &mut Config,
         repaint_signal: std::sync::Arc<ExampleRepaintSignal>,
-        audio_stream_handle: rodio::Sink,
-        av: &AudioVisual,
+        mut audio: Tracks,
+        av: AudioVisualStreams,
     ) -> Result<RealTimeState, Error> {
         let size = (config.window_size.0, config.window_size.1);
         println!("{}/{}", size.0, size.1);
@@ -53,7 +70,7 @@ impl RealTimeState {
             surface,
             queue,
             gui,
-        } = block_on(Setup::init(window, config));
+        } = block_on(Setup::init(window, config))?;
 
         let instance_shader = make_shader(&device, &config.instance_shader)?;
         let toy_shader = make_shader(&device, &config.toy_shader)?;
@@ -65,16 +82,21 @@ impl RealTimeState {
             wgpu::TextureFormat::Bgra8UnormSrgb,
         );
 
-        let op_streams = crate::op_stream::OpStream::from_vec_op4d(av);
-
         let renderpasses = make_renderpasses(
             &device,
-            op_streams,
+            av.op_streams.to_owned(),
             &instance_shader,
             config,
             wgpu::TextureFormat::Bgra8UnormSrgb,
         );
 
+        audio.link_streams(&renderpasses);
+        {
+            let mut s = gui.state.lock().unwrap();
+            s.streams = stream_states(&renderpasses, &[]);
+            s.stems = stem_states(&audio, &[]);
+        }
+
         let image_renderer = pollster::block_on(ImageRenderer::new(
             &device,
             &queue,
@@ -89,28 +111,134 @@ impl RealTimeState {
             count: 0,
             composition: Composition {
                 config: config.clone(),
-                camera: crate::camera::Camera::new(&config.cameras[0], size, &config, 0),
+                camera: crate::camera::Camera::new(config.cameras.camera(0), size, &config, 0),
                 renderpasses,
                 toy: Some(toy),
                 canvas: Canvas::init(size),
                 image_renderer: Some(image_renderer),
+                follow_path: false,
+                features: av.features(),
+                osc: None,
             },
             surface,
             gui,
             repaint_signal: repaint_signal.clone(),
-            audio_stream_handle,
+            audio,
+            reloader: None,
             mouse_pressed: false,
+            av,
+            midi: None,
+            selected_stream: 0,
         })
     }
 
+    /// Swaps in a re-rendered piece at the current playhead, keeping the
+    /// window, device and camera.
+    pub fn reload(&mut self, av: AudioVisualStreams) -> Result<(), Error> {
+        self.composition.features = av.features();
+        self.av = av;
+        self.restart()
+    }
+
+    /// Moves the playhead by `seconds`, within the piece.
+    pub fn seek(&mut self, seconds: f32) -> Result<(), Error> {
+        let time = (self.clock.current().total_elapsed + seconds).clamp(0.0, self.av.length);
+        self.clock.total_elapsed = Duration::from_secs_f32(time);
+        self.restart()
+    }
+
+    /// Restarts the op streams and audio of the piece at the playhead.
+    fn restart(&mut self) -> Result<(), Error> {
+        let time = self.clock.current().total_elapsed;
+        let instance_shader = make_shader(&self.device, &self.composition.config.instance_shader)?;
+
+        let mut renderpasses = make_renderpasses(
+            &self.device,
+            self.av.op_streams.to_owned(),
+            &instance_shader,
+            &mut self.composition.config,
+            wgpu::TextureFormat::Bgra8UnormSrgb,
+        );
+        renderpasses.iter_mut().for_each(|renderpass| {
+            renderpass.op_stream.get_batch(time);
+        });
+
+        let mut audio = Tracks::from_audiovisual(
+            &self.composition.config,
+            &self.av,
+            Duration::from_secs_f32(time),
+        )?;
+        audio.link_streams(&renderpasses);
+
+        let mut s = self.gui.state.lock().unwrap();
+        s.streams = stream_states(&renderpasses, &s.streams);
+        s.stems = stem_states(&audio, &s.stems);
+        audio.set_volume(&s);
+        if s.play {
+            audio.play();
+        }
+
+        self.composition.renderpasses = renderpasses;
+        self.audio = audio;
+        Ok(())
+    }
+
+    /// What `Save` writes and screenshots are saved with.
+    pub fn config_state(&self, s: &UiState) -> ConfigState {
+        ConfigState {
+            instance_mul: s.instance_mul,
+            stream_instance_muls: s
+                .streams
+                .iter()
+                .filter_map(|stream| {
+                    stream
+                        .instance_mul
+                        .map(|instance_mul| (stream.name.to_owned(), instance_mul))
+                })
+                .collect(),
+            seed: Some(self.composition.config.seed),
+        }
+    }
+
     pub fn play(&mut self) {
         self.clock.play();
-        self.audio_stream_handle.play();
+        self.audio.play();
     }
 
     #[allow(dead_code)]
     pub fn pause(&mut self) {
         self.clock.pause();
-        self.audio_stream_handle.pause();
+        self.audio.pause();
     }
 }
+
+/// One `StreamState` per renderpass, keeping the settings of streams that
+/// already existed by name.
+fn stream_states(renderpasses: &[RenderPassInput], previous: &[StreamState]) -> Vec<StreamState> {
+    renderpasses
+        .iter()
+        .map(|renderpass| {
+            let name = renderpass.op_stream.name();
+            previous
+                .iter()
+                .find(|stream| stream.name == name)
+                .cloned()
+                .unwrap_or_else(|| StreamState::init(name, renderpass.instance_mul))
+        })
+        .collect()
+}
+
+fn stem_states(audio: &Tracks, previous: &[StemState]) -> Vec<StemState> {
+    audio
+        .tracks
+        .iter()
+        .filter_map(|track| track.name.to_owned())
+        .map(|name| {
+            previous
+                .iter()
+                .find(|stem| stem.name == name)
+                .cloned()
+                .unwrap_or_else(|| StemState::init(name))
+        })
+        .collect()
+}
@@ -0,0 +1,75 @@
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use kintaro_egui_lib::UiState;
+
+use crate::application::cache::referenced_files;
+use crate::application::AudioVisualStreams;
+
+/// Watches a `.socool` file, and every file it references, and re-renders it
+/// on a background thread whenever one of them changes.
+pub struct Reloader {
+    pub receiver: Receiver<AudioVisualStreams>,
+}
+
+impl Reloader {
+    pub fn watch(
+        filename: String,
+        stems: bool,
+        render_cache: Option<String>,
+        state: Arc<Mutex<UiState>>,
+    ) -> Self {
+        let (sender, receiver) = channel();
+        std::thread::spawn(move || {
+            let mut last_modified = modified_times(&filename);
+            loop {
+                std::thread::sleep(Duration::from_millis(500));
+                let modified = modified_times(&filename);
+                if modified == last_modified {
+                    continue;
+                }
+                last_modified = modified;
+
+                set_status(&state, format!("rendering {}...", filename));
+                match AudioVisualStreams::from_socool(&filename, stems, render_cache.as_deref()) {
+                    Ok(av) => {
+                        set_status(&state, format!("reloaded {}", filename));
+                        if sender.send(av).is_err() {
+                            break;
+                        }
+                    }
+                    Err(e) => set_status(&state, format!("error rendering {}: {:?}", filename, e)),
+                }
+            }
+        });
+
+        Self { receiver }
+    }
+
+    pub fn try_recv(&self) -> Option<AudioVisualStreams> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+fn set_status(state: &Arc<Mutex<UiState>>, status: String) {
+    println!("{}", status);
+    state.lock().unwrap().status = Some(status);
+}
+
+fn modified_times(filename: &str) -> Vec<(PathBuf, Option<SystemTime>)> {
+    referenced_files(Path::new(filename))
+        .map(|files| {
+            files
+                .into_iter()
+                .map(|path| {
+                    let modified = std::fs::metadata(&path)
+                        .and_then(|metadata| metadata.modified())
+                        .ok();
+                    (path, modified)
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
@@ -1,7 +1,13 @@
 use std::{fs::File, io::Write, thread};
 
-use crate::{camera::Camera, clock::Clock, realtime::RealTimeState, save::ConfigState};
-use kintaro_egui_lib::{epi::App, ScreenDescriptor};
+use crate::camera::mode::CameraMode;
+use crate::composition::Composition;
+use crate::save::ConfigState;
+use crate::{clock::Clock, realtime::RealTimeState};
+use kintaro_egui_lib::{epi::App, CameraModeKind, PathAction, ScreenDescriptor, UiState};
+
+/// How far in front of the camera an orbit started from the GUI is centered.
+const ORBIT_DISTANCE: f32 = 300.0;
 
 /// A custom event type for the winit app.
 pub enum Event {
@@ -19,21 +25,24 @@ impl kintaro_egui_lib::epi::RepaintSignal for ExampleRepaintSignal {
 impl RealTimeState {
     pub fn render(&mut self, window: &winit::window::Window) -> Result<(), wgpu::SurfaceError> {
         self.clock.update();
-        self.audio_stream_handle
-            .set_volume(self.gui.state.lock().unwrap().volume);
-
+        if let Some(av) = self
+            .reloader
+            .as_ref()
+            .and_then(|reloader| reloader.try_recv())
         {
+            if let Err(e) = self.reload(av) {
+                self.gui.state.lock().unwrap().status = Some(format!("error reloading: {:?}", e));
+            }
+        }
+        self.audio.set_volume(&self.gui.state.lock().unwrap());
+
+        let screenshot = {
             let mut state = self.gui.state.lock().unwrap();
             if state.save {
-                let filename = "./save/saved.json";
-                let instance_mul = state.instance_mul.to_owned();
-                let camera = self.composition.camera.current_state().clone();
+                let filename = ConfigState::PATH;
+                let config_state = self.config_state(&state);
                 thread::spawn(move || {
                     let mut file = File::create(filename).unwrap();
-                    let config_state = ConfigState {
-                        camera,
-                        instance_mul,
-                    };
                     let serialized = serde_json::to_string(&config_state)
                         .expect(&format!("unable to serialize, {}", filename));
                     file.write(serialized.as_bytes())
@@ -42,6 +51,15 @@ impl RealTimeState {
                 state.save = false;
                 println!("Saved {}", filename);
             }
+            // Of the frame on screen, before it's advanced.
+            std::mem::take(&mut state.screenshot).then(|| self.config_state(&state))
+        };
+        if let Some(config_state) = screenshot {
+            let camera = self.composition.camera.current_state();
+            if let Err(e) = self.screenshot(camera, config_state) {
+                self.gui.state.lock().unwrap().status =
+                    Some(format!("error taking screenshot: {:?}", e));
+            }
         }
 
         let the_frame = self
@@ -58,6 +76,25 @@ impl RealTimeState {
                 label: Some("Command Encoder"),
             });
 
+        {
+            let s = self.gui.state.lock().unwrap();
+            let any_solo = s.any_solo();
+            self.composition
+                .renderpasses
+                .iter_mut()
+                .zip(s.streams.iter())
+                .for_each(|(renderpass, stream)| {
+                    renderpass.instance_mul = stream.instance_mul;
+                    renderpass.visible = stream.is_visible(any_solo);
+                });
+        }
+
+        let camera = &self.composition.camera;
+        let before = (
+            camera.index,
+            mode_kind(&camera.mode),
+            camera.projection.config(),
+        );
         self.composition.render(
             &self.device,
             &self.queue,
@@ -67,6 +104,32 @@ impl RealTimeState {
             self.gui.state.lock().unwrap().instance_mul,
             &view,
         );
+        // Cues and transitions can change the camera during render. Only
+        // those changes are synced, so edits made since the last frame, e.g.
+        // by keys, aren't lost.
+        {
+            let mut s = self.gui.state.lock().unwrap();
+            let camera = &self.composition.camera;
+            if camera.index != before.0 {
+                s.camera_index = camera.index;
+            }
+            let mode = mode_kind(&camera.mode);
+            if mode != before.1 {
+                s.camera_mode = mode.0;
+                if let Some(speed) = mode.1 {
+                    s.orbit_speed = speed;
+                }
+            }
+            if camera.projection.config() != before.2 {
+                s.projection = camera.projection.config();
+            }
+            // After syncing, so midi edits are applied like the GUI's.
+            if let Some(midi) = &self.midi {
+                if let Err(e) = self.composition.config.midi.update(midi, &mut s) {
+                    s.status = Some(format!("error saving midi mapping: {:?}", e));
+                }
+            }
+        }
 
         // // //TODO: Move to another file
         self.gui.platform.begin_frame();
@@ -127,26 +190,100 @@ impl RealTimeState {
         the_frame.present();
 
         {
-            let s = self.gui.state.lock().unwrap();
-            self.audio_stream_handle.set_volume(s.volume);
+            let mut s = self.gui.state.lock().unwrap();
+            if let Some(action) = s.camera_action.take() {
+                let current = self.composition.camera.current_state();
+                let cameras = &mut self.composition.config.cameras;
+                let selected = cameras.apply(action, s.camera_index, current);
+                if let Err(e) = cameras.save() {
+                    s.status = Some(format!("error saving camera presets: {:?}", e));
+                }
+                s.cameras = cameras.names();
+                s.camera_index = selected;
+                // Edits keep the current view instead of jumping to the selection.
+                self.composition.camera.index = selected;
+            }
+            if let Some(action) = s.path_action.take() {
+                let keyframe = self
+                    .composition
+                    .camera
+                    .keyframe(self.clock.current().total_elapsed);
+                let camera_path = &mut self.composition.config.camera_path;
+                match action {
+                    PathAction::Record => camera_path.insert(keyframe),
+                    PathAction::RemoveLast => camera_path.remove_last(),
+                    PathAction::Clear => camera_path.clear(),
+                }
+                if let Err(e) = camera_path.save() {
+                    s.status = Some(format!("error saving camera path: {:?}", e));
+                }
+                s.keyframes = camera_path.keyframes.len();
+            }
+            self.composition.follow_path = s.follow_path;
+            self.composition.camera.reactive.reactivity = s.reactivity;
+            if s.projection != self.composition.camera.projection.config() {
+                self.composition.camera.projection.set_config(&s.projection);
+            }
+            let select = s.selected_stream != self.selected_stream;
+            self.selected_stream = s.selected_stream;
+            set_camera_mode(&mut self.composition, &s, select);
             if s.camera_index != self.composition.camera.index {
-                self.composition.camera = Camera::new(
-                    &self.composition.config.cameras[s.camera_index],
-                    self.composition.config.window_size,
-                    &self.composition.config,
+                self.composition.camera.transition_to(
+                    self.composition.config.cameras.camera(s.camera_index),
                     s.camera_index,
-                )
+                    self.composition.config.camera_transition,
+                );
             }
-            if !s.play && !self.audio_stream_handle.is_paused() {
-                self.audio_stream_handle.pause();
+            if !s.play && !self.audio.is_paused() {
+                self.audio.pause();
             };
-            if s.play && self.audio_stream_handle.is_paused() {
-                self.audio_stream_handle.play();
+            if s.play && self.audio.is_paused() {
+                self.audio.play();
             };
             self.clock.set_playing(s.play);
-            self.audio_stream_handle.set_volume(s.volume);
+            self.audio.set_volume(&s);
         }
 
         Ok(())
     }
 }
+
+/// Switches the camera to the mode selected in the GUI. Orbits start
+/// around the point in front of the camera, so it doesn't jump. A LookAt
+/// camera only takes the selected stream when `select`ed or switched to.
+fn set_camera_mode(composition: &mut Composition, s: &UiState, select: bool) {
+    let stream = composition
+        .renderpasses
+        .get(s.selected_stream)
+        .map(|renderpass| renderpass.op_stream.name())
+        .unwrap_or_default();
+    let camera = &mut composition.camera;
+    match (&mut camera.mode, s.camera_mode) {
+        (CameraMode::Fly, CameraModeKind::Fly) => {}
+        (CameraMode::Orbit { speed, .. }, CameraModeKind::Orbit) => *speed = s.orbit_speed,
+        (CameraMode::LookAt { stream: current }, CameraModeKind::LookAt) => {
+            if select {
+                *current = stream
+            }
+        }
+        (_, CameraModeKind::Fly) => camera.mode = CameraMode::Fly,
+        (_, CameraModeKind::Orbit) => {
+            camera.mode = CameraMode::orbit_from(
+                camera.position,
+                camera.direction(),
+                ORBIT_DISTANCE,
+                s.orbit_speed,
+            )
+        }
+        (_, CameraModeKind::LookAt) => camera.mode = CameraMode::LookAt { stream },
+    }
+}
+
+/// The GUI's view of a mode, which doesn't change as an orbit turns.
+pub fn mode_kind(mode: &CameraMode) -> (CameraModeKind, Option<f32>) {
+    match mode {
+        CameraMode::Fly => (CameraModeKind::Fly, None),
+        CameraMode::Orbit { speed, .. } => (CameraModeKind::Orbit, Some(*speed)),
+        CameraMode::LookAt { .. } => (CameraModeKind::LookAt, None),
+    }
+}
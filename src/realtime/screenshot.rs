@@ -0,0 +1,146 @@
+use chrono::Local;
+use futures::executor::block_on;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use weresocool::error::Error;
+
+use crate::config::CameraConfig;
+use crate::print::write::{copy_texture_to_buffer, read_img};
+use crate::realtime::RealTimeState;
+use crate::save::ConfigState;
+
+pub const DIRECTORY: &str = "./screenshots";
+
+/// Written next to each screenshot. The flattened state is a `ConfigState`,
+/// so the file can be copied to `./save/saved.json` to return to the shot,
+/// and the camera can be added as a preset.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScreenshotMetadata {
+    pub filename: String,
+    /// Seconds into the piece.
+    pub time: f32,
+    pub size: (u32, u32),
+    pub camera: CameraConfig,
+    #[serde(flatten)]
+    pub state: ConfigState,
+}
+
+impl RealTimeState {
+    /// Draws the current frame again offscreen, without the GUI, at
+    /// `config.screenshot_size` or the window's size. The png and its
+    /// metadata are written to `DIRECTORY` on another thread, the returned
+    /// path is the png's.
+    ///
+    /// The offscreen texture starts cleared, so with `config.accumulation`
+    /// the shot has only the current frame, without the trails of earlier
+    /// ones. They live in the surface's texture, which can't be copied from.
+    pub fn screenshot(
+        &mut self,
+        camera: CameraConfig,
+        state: ConfigState,
+    ) -> Result<PathBuf, Error> {
+        let size = self.composition.config.screenshot_size.unwrap_or(self.size);
+        let max = self.device.limits().max_texture_dimension_2d;
+        if size.0 == 0 || size.1 == 0 || size.0 > max || size.1 > max {
+            return Err(Error::with_msg(format!(
+                "Unable to take a {}x{} screenshot, the adapter allows up to {}x{}",
+                size.0, size.1, max, max
+            )));
+        }
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            size: wgpu::Extent3d {
+                width: size.0,
+                height: size.1,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            // The renderpasses' pipelines are built for the surface's format.
+            format: wgpu::TextureFormat::Bgra8UnormSrgb,
+            usage: wgpu::TextureUsages::COPY_SRC | wgpu::TextureUsages::RENDER_ATTACHMENT,
+            label: Some("Screenshot Texture"),
+        });
+        let view = texture.create_view(&Default::default());
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Screenshot Encoder"),
+            });
+
+        self.composition.camera.projection.resize(size.0, size.1);
+        self.composition
+            .draw(&self.device, &self.queue, &mut encoder, &view);
+        self.composition
+            .camera
+            .projection
+            .resize(self.size.0, self.size.1);
+
+        let output_buffer = copy_texture_to_buffer(&mut encoder, size, &self.device, &texture);
+        self.queue.submit(Some(encoder.finish()));
+        let mut img = block_on(read_img(output_buffer, size, &self.device));
+        // Bgra to rgba. The window is opaque whatever the clear color's alpha.
+        img.pixels_mut().for_each(|pixel| {
+            pixel.0.swap(0, 2);
+            pixel.0[3] = 255;
+        });
+
+        let name = format!(
+            "{}-{}",
+            self.composition.config.filename,
+            Local::now().format("%Y%m%d-%H%M%S-%3f")
+        );
+        let png = Path::new(DIRECTORY).join(format!("{}.png", name));
+        let json = Path::new(DIRECTORY).join(format!("{}.json", name));
+        let metadata = ScreenshotMetadata {
+            filename: self.composition.config.filename.to_owned(),
+            time: self.clock.current().total_elapsed,
+            size,
+            camera,
+            state,
+        };
+        let metadata = serde_json::to_string_pretty(&metadata).map_err(|e| {
+            Error::with_msg(format!("Unable to serialize {}: {}", json.display(), e))
+        })?;
+        std::fs::create_dir_all(DIRECTORY)?;
+
+        let path = png.clone();
+        let ui_state = self.gui.state.clone();
+        let note = if self.composition.config.accumulation {
+            ", without accumulated trails"
+        } else {
+            ""
+        };
+        std::thread::spawn(move || {
+            let status = match (img.save(&png), std::fs::write(&json, metadata)) {
+                (Ok(_), Ok(_)) => format!("saved {}{}", png.display(), note),
+                (Err(e), _) => format!("error saving {}: {}", png.display(), e),
+                (_, Err(e)) => format!("error saving {}: {}", json.display(), e),
+            };
+            println!("{}", status);
+            ui_state.lock().unwrap().status = Some(status);
+        });
+        Ok(path)
+    }
+}
+
+#[test]
+fn test_metadata_loads_as_saved_state() {
+    let camera = crate::camera::default::default_cameras(vec![], Some((0.0, 20.0, 0.0)))[0].clone();
+    let metadata = ScreenshotMetadata {
+        filename: "kintaro".into(),
+        time: 12.5,
+        size: (3840, 2160),
+        camera,
+        state: ConfigState {
+            instance_mul: Default::default(),
+            stream_instance_muls: Default::default(),
+            seed: Some(3),
+        },
+    };
+    let json = serde_json::to_string(&metadata).unwrap();
+    let saved: ConfigState = serde_json::from_str(&json).unwrap();
+    assert_eq!(saved.seed, Some(3));
+    let shot: ScreenshotMetadata = serde_json::from_str(&json).unwrap();
+    assert_eq!(shot.camera.position, metadata.camera.position);
+}
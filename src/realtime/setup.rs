@@ -1,7 +1,10 @@
 use std::sync::{Arc, Mutex};
 
 use crate::config::Config;
+use crate::realtime::render::mode_kind;
+use crate::shared::request_device;
 use kintaro_egui_lib::{Platform, PlatformDescriptor, RenderPass, UiState};
+use weresocool::error::Error;
 use winit::window::Window;
 
 pub struct Gui {
@@ -19,31 +22,15 @@ pub struct Setup {
 }
 
 impl Setup {
-    pub async fn init(window: &Window, config: &Config) -> Self {
+    pub async fn init(window: &Window, config: &Config) -> Result<Self, Error> {
         let size = config.window_size;
-        let instance = wgpu::Instance::new(wgpu::Backends::all());
+        let instance = wgpu::Instance::new(config.device.backends);
         let surface = unsafe { instance.create_surface(window) };
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::default(),
-                compatible_surface: Some(&surface),
-                force_fallback_adapter: false,
-            })
-            .await
-            .unwrap();
-
-        let (device, queue) = adapter
-            .request_device(
-                &wgpu::DeviceDescriptor {
-                    label: None,
-                    features: wgpu::Features::empty(),
-                    limits: wgpu::Limits::default(),
-                },
-                None,
-            )
-            .await
-            .unwrap();
-        let surface_format = surface.get_preferred_format(&adapter).unwrap();
+        let (adapter, device, queue) =
+            request_device(&instance, &config.device, Some(&surface)).await?;
+        let surface_format = surface.get_preferred_format(&adapter).ok_or_else(|| {
+            Error::with_msg("Surface is incompatible with the adapter".to_string())
+        })?;
 
         let surface_config = wgpu::SurfaceConfiguration {
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
@@ -62,17 +49,33 @@ impl Setup {
             ..Default::default()
         });
         let renderpass = RenderPass::new(&device, surface_format, 1);
+        let (camera_mode, orbit_speed) = mode_kind(&config.cameras.camera(0).mode);
         let state = Arc::new(Mutex::new(kintaro_egui_lib::UiState {
             play: true,
             save: false,
             volume: config.volume,
             camera_index: 0,
+            cameras: config.cameras.names(),
+            camera_action: None,
+            follow_path: false,
+            keyframes: config.camera_path.keyframes.len(),
+            path_action: None,
+            camera_mode,
+            orbit_speed: orbit_speed.unwrap_or(10.0),
+            reactivity: config.camera_reactivity,
+            projection: config.cameras.camera(0).projection,
             instance_mul: config.instance_mul,
+            streams: vec![],
+            selected_stream: 0,
+            stems: vec![],
+            status: None,
             reset: false,
+            screenshot: false,
+            midi: Default::default(),
         }));
-        let app = kintaro_egui_lib::WrapApp::init(state.clone(), config.cameras.len());
+        let app = kintaro_egui_lib::WrapApp::init(state.clone());
 
-        Self {
+        Ok(Self {
             surface,
             device,
             queue,
@@ -82,6 +85,6 @@ impl Setup {
                 app,
                 state,
             },
-        }
+        })
     }
 }
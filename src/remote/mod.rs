@@ -0,0 +1,328 @@
+use kintaro_egui_lib::{InstanceMul, InstanceMulField, UiState};
+use serde::{Deserialize, Serialize};
+use std::io::{ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tungstenite::Message;
+use weresocool::error::Error;
+
+const PAGE: &str = include_str!("panel.html");
+/// How often each panel is checked for edits and sent changes.
+const POLL: Duration = Duration::from_millis(50);
+/// How long a client may take to send a request's head.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What the panel shows, sent whenever it changes.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RemoteState {
+    pub play: bool,
+    pub volume: f32,
+    pub camera_index: usize,
+    pub cameras: Vec<String>,
+    pub instance_mul: InstanceMul,
+    /// The slider range of each `instance_mul` field, by name.
+    pub ranges: Vec<(String, f32, f32)>,
+}
+
+impl RemoteState {
+    pub fn from_ui(state: &UiState) -> Self {
+        Self {
+            play: state.play,
+            volume: state.volume,
+            camera_index: state.camera_index,
+            cameras: state.cameras.to_owned(),
+            instance_mul: state.instance_mul,
+            ranges: InstanceMulField::ALL
+                .iter()
+                .map(|field| {
+                    let (lo, hi) = field.range();
+                    (field.name(), lo, hi)
+                })
+                .collect(),
+        }
+    }
+}
+
+/// An edit from the panel, e.g. `{"volume": 0.5}`. Missing fields are left
+/// as they are.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RemoteEdit {
+    pub play: Option<bool>,
+    pub volume: Option<f32>,
+    pub camera_index: Option<usize>,
+    /// One `instance_mul` field by name, e.g. `["size", 40]`, so edits from
+    /// other panels or the GUI to the other fields aren't overwritten.
+    pub instance_mul_field: Option<(String, f32)>,
+}
+
+impl RemoteEdit {
+    pub fn apply(&self, state: &mut UiState) {
+        if let Some(play) = self.play {
+            state.play = play;
+        }
+        if let Some(volume) = self.volume {
+            state.volume = volume.clamp(0.0, 1.0);
+        }
+        if let Some(idx) = self.camera_index.filter(|idx| *idx < state.cameras.len()) {
+            state.camera_index = idx;
+        }
+        if let Some((name, value)) = &self.instance_mul_field {
+            match InstanceMulField::from_name(name) {
+                Some(field) => field.set(&mut state.instance_mul, *value),
+                None => println!("remote panel: no instance_mul field {}", name),
+            }
+        }
+    }
+}
+
+/// Serves a control page for phones and other machines at `/`, which edits
+/// the GUI state over a WebSocket on the same address. Off by default,
+/// `--remote` serves it on `RemoteServer::ADDR`, localhost only, and
+/// `--remote-addr <addr>` anywhere else.
+pub struct RemoteServer {
+    pub addr: SocketAddr,
+}
+
+impl RemoteServer {
+    pub const ADDR: &'static str = "127.0.0.1:8080";
+
+    pub fn listen(addr: &str, state: Arc<Mutex<UiState>>) -> Result<Self, Error> {
+        let listener = TcpListener::bind(addr).map_err(|e| {
+            Error::with_msg(format!(
+                "Unable to serve the remote panel on {}: {}",
+                addr, e
+            ))
+        })?;
+        let addr = listener.local_addr()?;
+        println!("remote panel on http://{}", addr);
+        std::thread::spawn(move || {
+            listener
+                .incoming()
+                .filter_map(Result::ok)
+                .for_each(|stream| {
+                    let state = state.clone();
+                    std::thread::spawn(move || {
+                        if let Err(e) = serve(stream, state) {
+                            println!("remote panel: {:?}", e);
+                        }
+                    });
+                });
+        });
+        Ok(Self { addr })
+    }
+}
+
+/// Upgrades WebSocket requests and answers everything else with the page.
+fn serve(mut stream: TcpStream, state: Arc<Mutex<UiState>>) -> Result<(), Error> {
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    let head = peek_head(&stream)?;
+    let request = String::from_utf8_lossy(&head).to_lowercase();
+    if !same_origin(&request, stream.local_addr()?) {
+        stream.read_exact(&mut vec![0; head.len()])?;
+        stream.write_all(
+            b"HTTP/1.1 403 Forbidden\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        )?;
+        return Err(Error::with_msg(
+            "Refused a request from another origin".to_string(),
+        ));
+    }
+    if request.contains("upgrade: websocket") {
+        return serve_socket(stream, state);
+    }
+
+    // The head was only peeked, read it so closing doesn't reset the connection.
+    stream.read_exact(&mut vec![0; head.len()])?;
+    let response = if request.starts_with("get / ") {
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            PAGE.len(),
+            PAGE
+        )
+    } else {
+        "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()
+    };
+    stream.write_all(response.as_bytes())?;
+    Ok(())
+}
+
+/// The value of a header of a lowercased request head.
+fn header<'a>(request: &'a str, name: &str) -> Option<&'a str> {
+    request.lines().find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        (key.trim() == name).then(|| value.trim())
+    })
+}
+
+/// Whether a browser's request comes from a page served by this host, e.g.
+/// the panel, rather than from any site the browser has open. Clients other
+/// than browsers don't send an origin. The host must be the address the
+/// request arrived on, or localhost at its port, so a name rebound to this
+/// machine by another site is refused too.
+fn same_origin(request: &str, local: SocketAddr) -> bool {
+    let host = match header(request, "host") {
+        Some(host) => host,
+        None => return false,
+    };
+    let port = local.port();
+    let known = [
+        local.to_string(),
+        format!("localhost:{}", port),
+        format!("127.0.0.1:{}", port),
+    ];
+    if !known.iter().any(|known| known == host) {
+        return false;
+    }
+    match header(request, "origin") {
+        Some(origin) => origin.split_once("://").map_or(origin, |(_, host)| host) == host,
+        None => true,
+    }
+}
+
+/// The request line and headers, left in the stream for the handshake.
+fn peek_head(stream: &TcpStream) -> Result<Vec<u8>, Error> {
+    let deadline = Instant::now() + REQUEST_TIMEOUT;
+    let mut buf = [0u8; 4096];
+    loop {
+        let n = stream.peek(&mut buf)?;
+        if let Some(end) = buf[..n].windows(4).position(|w| w == b"\r\n\r\n") {
+            return Ok(buf[..end + 4].to_vec());
+        }
+        if n == 0 || n == buf.len() {
+            return Err(Error::with_msg(
+                "Invalid request to the remote panel".to_string(),
+            ));
+        }
+        if Instant::now() > deadline {
+            return Err(Error::with_msg(
+                "Timed out reading a request to the remote panel".to_string(),
+            ));
+        }
+        std::thread::sleep(Duration::from_millis(5));
+    }
+}
+
+fn serve_socket(stream: TcpStream, state: Arc<Mutex<UiState>>) -> Result<(), Error> {
+    let mut socket = tungstenite::accept(stream)
+        .map_err(|e| Error::with_msg(format!("WebSocket handshake failed: {}", e)))?;
+    socket.get_ref().set_read_timeout(Some(POLL))?;
+    let mut sent: Option<RemoteState> = None;
+    loop {
+        let current = RemoteState::from_ui(&state.lock().unwrap());
+        if sent.as_ref() != Some(&current) {
+            let json = serde_json::to_string(&current).map_err(|e| {
+                Error::with_msg(format!("Unable to serialize the remote state: {}", e))
+            })?;
+            socket
+                .write_message(Message::Text(json))
+                .map_err(|e| Error::with_msg(format!("Unable to send the remote state: {}", e)))?;
+            sent = Some(current);
+        }
+
+        match socket.read_message() {
+            Ok(Message::Text(text)) => match serde_json::from_str::<RemoteEdit>(&text) {
+                Ok(edit) => edit.apply(&mut state.lock().unwrap()),
+                Err(e) => println!("remote panel: invalid edit {}: {}", text, e),
+            },
+            Ok(Message::Close(_))
+            | Err(tungstenite::Error::ConnectionClosed)
+            | Err(tungstenite::Error::AlreadyClosed) => return Ok(()),
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+            Err(e) => return Err(Error::with_msg(format!("WebSocket error: {}", e))),
+        }
+    }
+}
+
+#[test]
+fn test_remote_panel_from_localhost_client() {
+    let state = Arc::new(Mutex::new(UiState {
+        play: true,
+        cameras: vec!["front".into(), "side".into()],
+        ..UiState::default()
+    }));
+    let server = RemoteServer::listen("127.0.0.1:0", state.clone()).unwrap();
+
+    let mut http = TcpStream::connect(server.addr).unwrap();
+    let get = format!(
+        "GET / HTTP/1.1\r\nHost: localhost:{}\r\n\r\n",
+        server.addr.port()
+    );
+    http.write_all(get.as_bytes()).unwrap();
+    let mut page = String::new();
+    http.read_to_string(&mut page).unwrap();
+    assert!(page.starts_with("HTTP/1.1 200 OK"));
+    assert!(page.contains("<html"));
+
+    let (mut socket, _) = tungstenite::connect(format!("ws://{}/", server.addr)).unwrap();
+    fn receive<S: Read + Write>(socket: &mut tungstenite::WebSocket<S>) -> RemoteState {
+        match socket.read_message().unwrap() {
+            Message::Text(text) => serde_json::from_str(&text).unwrap(),
+            message => panic!("unexpected message {:?}", message),
+        }
+    }
+    let initial = receive(&mut socket);
+    assert!(initial.play);
+    assert_eq!(initial.cameras, vec!["front", "side"]);
+
+    socket
+        .write_message(Message::Text(
+            r#"{"volume": 0.5, "camera_index": 1}"#.to_string(),
+        ))
+        .unwrap();
+    let echoed = receive(&mut socket);
+    assert_eq!((echoed.volume, echoed.camera_index), (0.5, 1));
+    assert_eq!(state.lock().unwrap().camera_index, 1);
+
+    // Only the edited field changes.
+    state.lock().unwrap().instance_mul.x = 3.0;
+    receive(&mut socket);
+    socket
+        .write_message(Message::Text(
+            r#"{"instance_mul_field": ["size", 40]}"#.to_string(),
+        ))
+        .unwrap();
+    let echoed = receive(&mut socket);
+    assert_eq!(
+        (echoed.instance_mul.x, echoed.instance_mul.size),
+        (3.0, 40.0)
+    );
+
+    // Changes from the GUI are pushed to the panel.
+    state.lock().unwrap().play = false;
+    assert!(!receive(&mut socket).play);
+}
+
+#[test]
+fn test_same_origin() {
+    let request = |origin: &str| {
+        format!(
+            "get / http/1.1\r\nhost: 127.0.0.1:8080\r\n{}upgrade: websocket\r\n\r\n",
+            origin
+        )
+    };
+    let local: SocketAddr = "127.0.0.1:8080".parse().unwrap();
+    assert!(same_origin(&request(""), local));
+    assert!(same_origin(
+        &request("origin: http://127.0.0.1:8080\r\n"),
+        local
+    ));
+    assert!(!same_origin(
+        &request("origin: https://example.com\r\n"),
+        local
+    ));
+    assert!(!same_origin(
+        &request("origin: http://127.0.0.1:8081\r\n"),
+        local
+    ));
+
+    // A site rebinding its name to this machine sends its own host and origin.
+    let rebound = "get / http/1.1\r\nhost: evil:8080\r\norigin: http://evil:8080\r\nupgrade: websocket\r\n\r\n";
+    assert!(!same_origin(rebound, local));
+    let lan: SocketAddr = "192.168.1.5:8080".parse().unwrap();
+    let phone =
+        "get / http/1.1\r\nhost: 192.168.1.5:8080\r\norigin: http://192.168.1.5:8080\r\n\r\n";
+    assert!(same_origin(phone, lan));
+}
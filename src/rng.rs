@@ -0,0 +1,32 @@
+use rand::{Rng, SeedableRng};
+
+/// The rng threaded through shapes, colors and instancers. Generation is
+/// reproducible for a given seed. `StdRng`'s algorithm may change between rand
+/// releases, ChaCha8's output is fixed, so saved seeds keep their look.
+pub type GenRng = rand_chacha::ChaCha8Rng;
+
+pub fn random_seed() -> u64 {
+    rand::thread_rng().gen()
+}
+
+/// An rng for one op stream, so that a stream's generation doesn't depend on
+/// how many values the other streams drew.
+pub fn stream_rng(seed: u64, name: &str) -> GenRng {
+    GenRng::seed_from_u64(seed ^ fnv1a(name.as_bytes()))
+}
+
+/// A hash that is stable across platforms and compiler versions, unlike
+/// `DefaultHasher`.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[test]
+fn test_stream_rng_is_seeded_by_name() {
+    let draw = |mut rng: GenRng| (0..4).map(|_| rng.gen::<u32>()).collect::<Vec<_>>();
+    assert_eq!(draw(stream_rng(7, "a")), draw(stream_rng(7, "a")));
+    assert_ne!(draw(stream_rng(7, "a")), draw(stream_rng(7, "b")));
+    assert_ne!(draw(stream_rng(7, "a")), draw(stream_rng(8, "a")));
+}
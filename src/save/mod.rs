@@ -1,17 +1,26 @@
 use kintaro_egui_lib::InstanceMul;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 use crate::config::CameraConfig;
 
+/// What `Save` writes to `./save/saved.json`. Cameras aren't part of it, they
+/// are saved per composition as `CameraPresets`. Files from before that still
+/// load, their camera is moved to the presets by `saved_camera`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConfigState {
-    pub camera: CameraConfig,
     pub instance_mul: InstanceMul,
+    #[serde(default)]
+    pub stream_instance_muls: BTreeMap<String, InstanceMul>,
+    #[serde(default)]
+    pub seed: Option<u64>,
 }
 
 impl ConfigState {
+    pub const PATH: &'static str = "./save/saved.json";
+
     pub fn load_saved() -> Result<Option<ConfigState>, serde_json::Error> {
-        let path = "./save/saved.json";
+        let path = Self::PATH;
         if std::path::Path::new(path).is_file() {
             println!("Loading saved state from {}", path);
             let saved_data = std::fs::read_to_string(path).expect("Unable to read file");
@@ -21,4 +30,36 @@ impl ConfigState {
             Ok(None)
         }
     }
+
+    /// The camera of a `saved.json` written before cameras were saved per
+    /// composition, if it has one.
+    pub fn saved_camera() -> Option<CameraConfig> {
+        let data = std::fs::read_to_string(Self::PATH).ok()?;
+        legacy_camera(&data)
+    }
+}
+
+fn legacy_camera(data: &str) -> Option<CameraConfig> {
+    let mut saved: serde_json::Value = serde_json::from_str(data).ok()?;
+    let camera = saved.get_mut("camera")?.take();
+    match serde_json::from_value(camera) {
+        Ok(camera) => Some(camera),
+        Err(e) => {
+            println!("Ignoring the camera in {}: {}", ConfigState::PATH, e);
+            None
+        }
+    }
+}
+
+#[test]
+fn test_legacy_camera() {
+    let old = r#"{
+        "camera": {"position": [1.0, 2.0, 3.0], "yaw": 0.5, "pitch": 0.0},
+        "instance_mul": {"x": 9.0, "y": 19.0, "z": 1.0, "life": 2.0, "size": 23.0, "length": 1.0}
+    }"#;
+    assert_eq!(legacy_camera(old).unwrap().position, (1.0, 2.0, 3.0));
+    assert!(serde_json::from_str::<ConfigState>(old).is_ok());
+
+    let new = r#"{"instance_mul": {"x": 9.0, "y": 19.0, "z": 1.0, "life": 2.0, "size": 23.0, "length": 1.0}}"#;
+    assert!(legacy_camera(new).is_none());
 }
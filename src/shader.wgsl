@@ -25,6 +25,9 @@ struct InstanceInput {
     [[location(9)]] life: f32;
     [[location(10)]] size: f32;
     [[location(11)]] length: f32;
+    [[location(12)]] envelope_size: f32;
+    [[location(13)]] alpha: f32;
+    [[location(14)]] intensity: f32;
 };
 
 [[stage(vertex)]]
@@ -43,7 +46,7 @@ fn vs_main(
       pow(model.color[0] * instance.life, 3.0),
       pow(model.color[1] * instance.life, 3.0),
       pow(model.color[2] * instance.life, 3.0),
-  );
+  ) * instance.intensity;
 
   if (instance.life < 1.99) {
   out.color = vec4<f32>(color_matrix, instance.life);
@@ -54,11 +57,13 @@ fn vs_main(
          1.0 * instance.life, 
        ), instance.life);
  };
+  out.color = vec4<f32>(out.color.rgb * instance.alpha, out.color.a * instance.alpha);
 
+  let size = instance.size * instance.envelope_size;
   let scale = mat4x4<f32>(
-      vec4<f32>(instance.size, 0.0, 0.0, 0.0),
-      vec4<f32>(0.0, instance.size, 0.0, 0.0),
-      vec4<f32>(0.0, 0.0, instance.size, 0.0),
+      vec4<f32>(size, 0.0, 0.0, 0.0),
+      vec4<f32>(0.0, size, 0.0, 0.0),
+      vec4<f32>(0.0, 0.0, size, 0.0),
       vec4<f32>(0.0, 0.0, 0.0, 1.0)
   );
 
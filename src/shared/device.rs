@@ -0,0 +1,179 @@
+use weresocool::error::Error;
+
+/// When to use a software adapter instead of a gpu.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Fallback {
+    /// Only hardware adapters.
+    Never,
+    /// A hardware adapter if there is one, otherwise the fallback adapter.
+    Allow,
+    /// Only the fallback adapter, e.g. for reproducible renders on CI.
+    Only,
+}
+
+/// How to pick the adapter that realtime, print and test rendering run on.
+/// `with_env` overrides its fields with
+///
+/// - `KINTARO_BACKEND`: comma separated `vulkan`, `metal`, `dx12`, `dx11`,
+///   `gl`, `primary`, `secondary` or `all`
+/// - `KINTARO_ADAPTER`: part of the adapter name, case insensitive
+/// - `KINTARO_POWER`: `low` or `high`
+/// - `KINTARO_FALLBACK`: `never`, `allow` or `only`
+#[derive(Clone, Debug)]
+pub struct DevicePolicy {
+    pub backends: wgpu::Backends,
+    pub power_preference: wgpu::PowerPreference,
+    pub fallback: Fallback,
+    pub adapter_name: Option<String>,
+}
+
+impl Default for DevicePolicy {
+    fn default() -> Self {
+        Self {
+            backends: wgpu::Backends::all(),
+            power_preference: wgpu::PowerPreference::default(),
+            fallback: Fallback::Allow,
+            adapter_name: None,
+        }
+    }
+}
+
+impl DevicePolicy {
+    pub fn with_env(self) -> Result<Self, Error> {
+        let mut policy = self;
+        let var = |name: &str| std::env::var(name).ok().map(|value| value.to_lowercase());
+
+        if let Some(backends) = var("KINTARO_BACKEND") {
+            policy.backends = parse_backends(&backends)?;
+        }
+        if let Some(name) = var("KINTARO_ADAPTER") {
+            policy.adapter_name = Some(name);
+        }
+        if let Some(power) = var("KINTARO_POWER") {
+            policy.power_preference = match power.as_str() {
+                "low" => wgpu::PowerPreference::LowPower,
+                "high" => wgpu::PowerPreference::HighPerformance,
+                _ => return Err(Error::with_msg(format!("invalid KINTARO_POWER: {}", power))),
+            };
+        }
+        if let Some(fallback) = var("KINTARO_FALLBACK") {
+            policy.fallback = match fallback.as_str() {
+                "never" => Fallback::Never,
+                "allow" => Fallback::Allow,
+                "only" => Fallback::Only,
+                _ => {
+                    return Err(Error::with_msg(format!(
+                        "invalid KINTARO_FALLBACK: {}",
+                        fallback
+                    )))
+                }
+            };
+        }
+        Ok(policy)
+    }
+}
+
+pub fn parse_backends(backends: &str) -> Result<wgpu::Backends, Error> {
+    backends
+        .split(',')
+        .map(str::trim)
+        .filter(|backend| !backend.is_empty())
+        .try_fold(wgpu::Backends::empty(), |acc, backend| {
+            let backend = match backend.to_lowercase().as_str() {
+                "vulkan" | "vk" => wgpu::Backends::VULKAN,
+                "metal" => wgpu::Backends::METAL,
+                "dx12" | "d3d12" => wgpu::Backends::DX12,
+                "dx11" | "d3d11" => wgpu::Backends::DX11,
+                "gl" | "opengl" | "gles" => wgpu::Backends::GL,
+                "primary" => wgpu::Backends::PRIMARY,
+                "secondary" => wgpu::Backends::SECONDARY,
+                "all" => wgpu::Backends::all(),
+                other => return Err(Error::with_msg(format!("unknown wgpu backend: {}", other))),
+            };
+            Ok(acc | backend)
+        })
+}
+
+/// Picks an adapter following the policy and requests a device on it.
+pub async fn request_device(
+    instance: &wgpu::Instance,
+    policy: &DevicePolicy,
+    compatible_surface: Option<&wgpu::Surface>,
+) -> Result<(wgpu::Adapter, wgpu::Device, wgpu::Queue), Error> {
+    let adapter = request_adapter(instance, policy, compatible_surface)
+        .await
+        .ok_or_else(|| {
+            Error::with_msg(format!(
+                "No wgpu adapter found for backends {:?}, adapter {:?}, fallback {:?}",
+                policy.backends, policy.adapter_name, policy.fallback
+            ))
+        })?;
+
+    let info = adapter.get_info();
+    println!(
+        "using adapter: {} ({:?}, {:?})",
+        info.name, info.backend, info.device_type
+    );
+
+    let (device, queue) = adapter
+        .request_device(
+            &wgpu::DeviceDescriptor {
+                label: None,
+                features: wgpu::Features::empty(),
+                limits: wgpu::Limits::default(),
+            },
+            None,
+        )
+        .await
+        .map_err(|e| {
+            Error::with_msg(format!("Unable to request device on {}: {}", info.name, e))
+        })?;
+
+    Ok((adapter, device, queue))
+}
+
+async fn request_adapter(
+    instance: &wgpu::Instance,
+    policy: &DevicePolicy,
+    compatible_surface: Option<&wgpu::Surface>,
+) -> Option<wgpu::Adapter> {
+    if let Some(name) = &policy.adapter_name {
+        return instance
+            .enumerate_adapters(policy.backends)
+            .filter(|adapter| {
+                compatible_surface.map_or(true, |surface| adapter.is_surface_supported(surface))
+            })
+            .find(|adapter| adapter.get_info().name.to_lowercase().contains(name));
+    }
+
+    let request = |force_fallback_adapter| {
+        instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: policy.power_preference,
+            compatible_surface,
+            force_fallback_adapter,
+        })
+    };
+    match policy.fallback {
+        Fallback::Never => request(false)
+            .await
+            .filter(|adapter| adapter.get_info().device_type != wgpu::DeviceType::Cpu),
+        Fallback::Only => request(true).await,
+        Fallback::Allow => match request(false).await {
+            Some(adapter) => Some(adapter),
+            None => {
+                println!("no hardware adapter found, trying the fallback adapter");
+                request(true).await
+            }
+        },
+    }
+}
+
+#[test]
+fn test_parse_backends() {
+    assert_eq!(
+        parse_backends("vulkan, gl").unwrap(),
+        wgpu::Backends::VULKAN | wgpu::Backends::GL
+    );
+    assert_eq!(parse_backends("all").unwrap(), wgpu::Backends::all());
+    assert!(parse_backends("glide").is_err());
+}
@@ -1,7 +1,9 @@
+mod device;
 pub mod helpers;
 mod render_pass;
 mod render_pipeline;
 
+pub use device::{parse_backends, request_device, DevicePolicy, Fallback};
 pub use helpers::{make_color_attachments, new_random_clear_color, new_random_indices};
 pub use render_pass::RenderPassInput;
 pub use render_pipeline::create_render_pipeline;
@@ -1,8 +1,12 @@
 use crate::config::Config;
+use crate::instance::envelope::Envelope;
+use crate::instance::instancer::Instancer;
 use crate::instance::Instance;
 use crate::op_stream::OpStream;
+use crate::rng::GenRng;
 use crate::vertex::shape::Shape;
 use crate::vertex::Vertex;
+use kintaro_egui_lib::InstanceMul;
 
 use super::make_color_attachments;
 
@@ -14,6 +18,14 @@ pub struct RenderPassInput {
     pub index_buffer: wgpu::Buffer,
     pub instance_buffer: wgpu::Buffer,
     pub instances: Vec<Instance>,
+    pub instancer: Box<dyn Instancer>,
+    /// Overrides the global `InstanceMul` for this stream when set.
+    pub instance_mul: Option<InstanceMul>,
+    pub envelope: Envelope,
+    /// Seeded from `Config::seed` and the stream name.
+    pub rng: GenRng,
+    /// Invisible renderpasses still consume their ops but are not drawn.
+    pub visible: bool,
     pub uniforms: crate::uniforms::RealtimeUniforms,
     pub uniform_bind_group: wgpu::BindGroup,
     pub uniform_buffer: wgpu::Buffer,
@@ -0,0 +1,68 @@
+use indexmap::IndexMap;
+
+use crate::color::NamedValue;
+use crate::op_stream::OpStream;
+
+/// Maps op stream names to values, the same way `ColorMap` maps names to
+/// `GenColor`. The first entry whose name is one of the stream's names wins,
+/// otherwise `default` is used.
+#[derive(Clone, Debug)]
+pub struct StreamMap<T> {
+    pub values: IndexMap<String, T>,
+    pub default: T,
+}
+
+impl<T> StreamMap<T> {
+    pub fn init(default: T) -> Self {
+        Self {
+            values: IndexMap::new(),
+            default,
+        }
+    }
+
+    pub fn from_named(values: Vec<NamedValue<T>>, default: T) -> Self {
+        let mut map: IndexMap<String, T> = IndexMap::new();
+        values.into_iter().for_each(|(name, value)| {
+            map.insert(name.to_string(), value);
+        });
+
+        Self {
+            values: map,
+            default,
+        }
+    }
+
+    pub fn with(mut self, name: &str, value: T) -> Self {
+        self.values.insert(name.to_string(), value);
+        self
+    }
+
+    pub fn get(&self, op_stream: &OpStream) -> &T {
+        self.get_by_names(&op_stream.names)
+    }
+
+    pub fn get_by_names(&self, names: &[String]) -> &T {
+        for (name, value) in self.values.iter() {
+            if names.contains(name) {
+                return value;
+            }
+        }
+        &self.default
+    }
+
+    pub fn get_mut(&mut self, op_stream: &OpStream) -> &mut T {
+        let idx = self
+            .values
+            .keys()
+            .position(|name| op_stream.names.contains(name));
+        match idx {
+            Some(idx) => {
+                self.values
+                    .get_index_mut(idx)
+                    .expect("stream map index out of bounds")
+                    .1
+            }
+            None => &mut self.default,
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use crate::{
     gen::{GenColor, GenIndex, GenPosition, Index},
     op_stream::OpStream,
+    rng::GenRng,
 };
 use rand::prelude::*;
 
@@ -33,15 +34,13 @@ pub struct Position {
 }
 
 impl GenIndex for RandIndex {
-    fn gen(&self, n_vertices: usize) -> u16 {
-        let mut rng = rand::thread_rng();
+    fn gen(&self, n_vertices: usize, rng: &mut GenRng) -> u16 {
         rng.gen_range(0..n_vertices as u16)
     }
 }
 
 impl GenPosition for RandPosition {
-    fn gen(&self) -> Position {
-        let mut rng = rand::thread_rng();
+    fn gen(&self, rng: &mut GenRng) -> Position {
         // let mut xrng = rand::thread_rng();
         // let mut yrng = rand::thread_rng();
         // let mut zrng = rand::thread_rng();
@@ -60,15 +59,15 @@ impl GenPosition for RandPosition {
 }
 
 impl Shape {
-    pub fn gen(&mut self, op_stream: &OpStream) -> ShapeGenResult {
+    pub fn gen(&mut self, op_stream: &OpStream, rng: &mut GenRng) -> ShapeGenResult {
         ShapeGenResult {
             vertices: (0..self.n_vertices)
                 .into_iter()
-                .map(|_| Vertex::from_shape(self, op_stream))
+                .map(|_| Vertex::from_shape(self, op_stream, rng))
                 .collect(),
             indices: (0..self.n_indices)
                 .into_iter()
-                .map(|_| self.indices.gen(self.n_indices))
+                .map(|_| self.indices.gen(self.n_indices, rng))
                 .collect(),
         }
     }
@@ -85,10 +84,9 @@ impl Shape {
 }
 
 impl Vertex {
-    pub fn from_shape(shape: &mut Shape, op_stream: &OpStream) -> Self {
-        let position = shape.position.gen();
-        let color = shape.color.gen(op_stream);
-        let mut rng = rand::thread_rng();
+    pub fn from_shape(shape: &mut Shape, op_stream: &OpStream, rng: &mut GenRng) -> Self {
+        let position = shape.position.gen(rng);
+        let color = shape.color.gen(op_stream, rng);
         let mut r = || rng.gen::<f32>() * 2.0 - 1.0;
         Self {
             position: [position.x, position.y, position.z],
@@ -102,3 +100,30 @@ impl Vertex {
         }
     }
 }
+
+#[test]
+fn test_seeded_generation_is_identical() {
+    use crate::color::ColorSet;
+    use crate::rng::stream_rng;
+
+    let op_stream = OpStream {
+        names: vec!["a".into()],
+        ops: vec![],
+        length: 1.0,
+    };
+    let generate = || {
+        let mut shape = Shape {
+            n_vertices: 30,
+            n_indices: 90,
+            position: Box::new(RandPosition),
+            color: Box::new(ColorSet::init(vec!["#ff0088", "#112233", "#445566"])),
+            indices: Box::new(RandIndex),
+        };
+        let mut rng = stream_rng(42, &op_stream.name());
+        let result = shape.gen(&op_stream, &mut rng);
+        let mut bytes: Vec<u8> = bytemuck::cast_slice(&result.vertices).to_vec();
+        bytes.extend_from_slice(bytemuck::cast_slice(&result.indices));
+        bytes
+    };
+    assert_eq!(generate(), generate());
+}